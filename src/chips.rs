@@ -0,0 +1,154 @@
+//! Public entry points for the chips and circuits of the examples.
+//!
+//! The `eN_*` modules stay private so they can keep reading like tutorials;
+//! everything that is meant to be used from other crates is re-exported here.
+
+/// The "mini plonk" gate `l * sl + r * sr + l * r * sm - o * so + sc = 0`
+/// and the circuits built on it.
+pub mod plonk {
+    pub use crate::e0_tutorial_answer::{
+        TutorialBuilder, TutorialCell, TutorialChip, TutorialCircuit, TutorialComposer,
+        TutorialConfig, TutorialConstantCircuit,
+    };
+
+    /// Polynomial expressions compiled to TutorialComposer gates.
+    pub mod expression {
+        pub use crate::e0_tutorial_answer::{
+            Expr, ExpressionCircuit, ExpressionCompiler, ParseError, Wire,
+        };
+    }
+
+    /// circom `.r1cs` / `.wtns` files imported as TutorialChip circuits.
+    pub mod r1cs {
+        pub use crate::e0_tutorial_answer::{
+            read_r1cs, read_wtns, LinearCombination, R1cs, R1csCircuit, R1csConstraint,
+        };
+    }
+    pub use crate::e2_vitalik_example::TutorialCircuit as VitalikCircuit;
+}
+
+/// Fibonacci sequences `F(0) = a`, `F(1) = b`, `F(i + 2) = F(i) + F(i + 1)`.
+pub mod fibonacci {
+    pub use crate::e3_fibonacci_ex2::fibonacci;
+
+    /// Three advice columns `a | b | c`, one region per term.
+    pub mod multi_column {
+        pub use crate::e3_fibonacci_ex1::{
+            FibonacciChip, FibonacciConfig, MyCircuit as FibonacciCircuit,
+        };
+    }
+
+    /// A single advice column queried with `Rotation`, one region for the whole table.
+    pub mod single_column {
+        pub use crate::e3_fibonacci_ex2::{
+            FibonacciChip, FibonacciConfig, MyCircuit as FibonacciCircuit,
+        };
+    }
+
+    /// Repeated squaring of `[[1, 1], [1, 0]]` over the bits of `n`,
+    /// `O(log n)` rows.
+    pub mod matrix {
+        pub use crate::e3_fibonacci_matrix::{
+            FibonacciMatrixChip, FibonacciMatrixCircuit, FibonacciMatrixConfig,
+        };
+    }
+
+    /// A table laid out to `max_n` with a private stop index `n`, proving
+    /// `F(n) = y` without revealing `n`.
+    pub mod private_index {
+        pub use crate::e3_fibonacci_private_index::{
+            PrivateIndexChip, PrivateIndexCircuit, PrivateIndexConfig,
+        };
+    }
+
+    /// Order-`D` linear recurrences with the coefficients in fixed columns,
+    /// e.g. Fibonacci, Lucas, Tribonacci and Pell.
+    pub mod linear_recurrence {
+        pub use crate::e3_linear_recurrence::{
+            linear_recurrence, LinearRecurrenceChip, LinearRecurrenceCircuit,
+            LinearRecurrenceConfig,
+        };
+    }
+}
+
+/// Range checks `0 <= v < RANGE`.
+pub mod range {
+    /// Range check with a degree-`RANGE` polynomial gate.
+    pub mod simple {
+        pub use crate::e4_simple_rangecheck::{
+            BatchCircuit, Mycircuit as RangeCheckCircuit, RangeCheckConfig, RangeConstrained,
+        };
+    }
+
+    /// Range check with either the polynomial gate or a lookup table.
+    pub mod lookup {
+        pub use crate::e5_lookup_rangecheck::{
+            BatchCircuit, MyCircuit as RangeCheckCircuit, RangeCheckConfig, RangeConstrained,
+            RangeTableConfig,
+        };
+    }
+
+    /// `a < b`, `a <= b` and `lo <= a < hi` on values checked by `lookup`.
+    pub mod compare {
+        pub use crate::e5_lookup_rangecheck::{CompareCircuit, CompareConfig};
+    }
+
+    /// Range check of any width up to `K` bits against one `(num_bits, value)` table.
+    pub mod tagged {
+        pub use crate::e5_lookup_rangecheck::{
+            TaggedCircuit as RangeCheckCircuit, TaggedRangeCheckConfig, TaggedRangeConstrained,
+            TaggedRangeTableConfig,
+        };
+    }
+
+    /// Range check of any width below the field size, with `K`-bit limbs
+    /// looked up in the table of `lookup`.
+    pub mod running_sum {
+        pub use crate::e7_running_sum_rangecheck::{
+            MyCircuit as RangeCheckCircuit, RunningSum, RunningSumConfig,
+        };
+    }
+
+    /// `0 <= v < bound` with the bound chosen per call at synthesis time.
+    pub mod dynamic {
+        pub use crate::e8_dynamic_rangecheck::{
+            Bounded, DynamicRangeConfig, MyCircuit as RangeCheckCircuit,
+        };
+    }
+}
+
+/// `a ^ b`, `a & b` and `a | b` on `0..RANGE` values, one lookup each.
+pub mod bitwise {
+    pub use crate::e5_lookup_rangecheck::{
+        BitwiseCircuit, BitwiseConfig, BitwiseOp, BitwiseTableConfig,
+    };
+}
+
+/// Lookup tables of any number of columns, loaded from CSV, JSON, binary
+/// files or iterators.
+pub mod table {
+    pub use crate::e5_lookup_rangecheck::{Table, TableCircuit, TableCircuitConfig, TableConfig};
+}
+
+/// `value == 0` and `a == b` as boolean cells, from an inverse witness.
+pub mod is_zero {
+    pub use crate::e9_is_zero::{
+        IsEqualChip, IsEqualConfig, IsZeroChip, IsZeroConfig, MyCircuit as IsZeroCircuit,
+    };
+}
+
+/// Bit decomposition and AND/OR/XOR/NOT over boolean cells.
+pub mod bits {
+    pub use crate::e10_bits::{Bit, Bits, BitsChip, BitsConfig, MyCircuit as BitsCircuit};
+}
+
+/// Poseidon hash over bn256 (through `halo2-base`).
+pub mod poseidon {
+    pub use crate::e6_poseidon_test::HashCircuit;
+}
+
+/// Lookups into a table witnessed in advice columns, so its contents can
+/// change per proof (through `halo2-base`).
+pub mod dynamic_lookup {
+    pub use crate::e11_dynamic_lookup::{DynamicLookupCircuit, DynamicLookupConfig};
+}
@@ -7,33 +7,88 @@ use halo2_proofs::{
 };
 use std::marker::PhantomData;
 
-#[allow(non_snake_case, dead_code)]
+mod e0_builder;
+mod e0_expression;
+mod e0_r1cs;
+pub use e0_builder::*;
+pub use e0_expression::*;
+pub use e0_r1cs::*;
+
+#[allow(non_snake_case)]
 #[derive(Debug, Clone)]
-struct TutorialConfig {
-    l: Column<Advice>,
-    r: Column<Advice>,
-    o: Column<Advice>,
-
-    sl: Column<Fixed>,
-    sr: Column<Fixed>,
-    so: Column<Fixed>,
-    sm: Column<Fixed>,
-    sc: Column<Fixed>,
-    PI: Column<Instance>,
+pub struct TutorialConfig {
+    pub l: Column<Advice>,
+    pub r: Column<Advice>,
+    pub o: Column<Advice>,
+
+    pub sl: Column<Fixed>,
+    pub sr: Column<Fixed>,
+    pub so: Column<Fixed>,
+    pub sm: Column<Fixed>,
+    pub sc: Column<Fixed>,
+    pub PI: Column<Instance>,
 }
 
-struct TutorialChip<F: FieldExt> {
+pub struct TutorialChip<F: FieldExt> {
     config: TutorialConfig,
     marker: PhantomData<F>,
 }
 
 impl<F: FieldExt> TutorialChip<F> {
-    fn new(config: TutorialConfig) -> Self {
+    pub fn new(config: TutorialConfig) -> Self {
         TutorialChip {
             config,
             marker: PhantomData,
         }
     }
+
+    /// Allocates the columns and the "mini plonk" gate
+    /// `l * sl + r * sr + l * r * sm - o * so + sc = 0`.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> TutorialConfig {
+        let l = meta.advice_column();
+        let r = meta.advice_column();
+        let o = meta.advice_column();
+
+        meta.enable_equality(l);
+        meta.enable_equality(r);
+        meta.enable_equality(o);
+
+        let sm = meta.fixed_column();
+        let sl = meta.fixed_column();
+        let sr = meta.fixed_column();
+        let so = meta.fixed_column();
+        let sc = meta.fixed_column();
+
+        #[allow(non_snake_case)]
+        let PI = meta.instance_column();
+        meta.enable_equality(PI);
+
+        meta.create_gate("mini plonk", |meta| {
+            let l = meta.query_advice(l, Rotation::cur());
+            let r = meta.query_advice(r, Rotation::cur());
+            let o = meta.query_advice(o, Rotation::cur());
+
+            let sl = meta.query_fixed(sl, Rotation::cur());
+            let sr = meta.query_fixed(sr, Rotation::cur());
+            let so = meta.query_fixed(so, Rotation::cur());
+            let sm = meta.query_fixed(sm, Rotation::cur());
+            let sc = meta.query_fixed(sc, Rotation::cur());
+
+            vec![l.clone() * sl + r.clone() * sr + l * r * sm + (o * so * (-F::one())) + sc]
+        });
+
+        TutorialConfig {
+            l,
+            r,
+            o,
+            sl,
+            sr,
+            so,
+            sm,
+            sc,
+            PI,
+        }
+    }
 }
 
 impl<F: FieldExt> Chip<F> for TutorialChip<F> {
@@ -49,7 +104,12 @@ impl<F: FieldExt> Chip<F> for TutorialChip<F> {
     }
 }
 
-trait TutorialComposer<F: FieldExt> {
+/// Gate-by-gate API of the TutorialChip.
+///
+/// Every call opens its own region, so each gate and each `copy` takes a
+/// region of its own and nothing is packed across calls. Use
+/// [`TutorialChip::assign_packed`] to lay out many gates in one region.
+pub trait TutorialComposer<F: FieldExt> {
     fn raw_multiply<FM>(
         &self,
         layouter: &mut impl Layouter<F>,
@@ -76,8 +136,72 @@ trait TutorialComposer<F: FieldExt> {
         cell: Cell,
         row: usize,
     ) -> Result<(), Error>;
+
+    /// `l + constant = o`, with the constant baked into the `sc` column.
+    fn raw_add_constant<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        constant: F,
+        f: FM,
+    ) -> Result<(Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>)>;
+
+    /// `l * constant = o`, with the constant baked into the `sl` column.
+    fn raw_mul_constant<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        constant: F,
+        f: FM,
+    ) -> Result<(Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>)>;
+
+    /// Ensure a wire holds `constant`, without making the constant a public input.
+    fn assert_constant(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cell: Cell,
+        constant: F,
+    ) -> Result<(), Error>;
+
+    /// `l - r = o`.
+    fn raw_sub<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        f: FM,
+    ) -> Result<(Cell, Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>;
+
+    /// `l * r = 1`, i.e. `r` is the inverse of `l`. `f` returns `(x, 1 / x)`.
+    ///
+    /// Returns `Error::Synthesis` if `x` is known to be zero, since no inverse
+    /// would satisfy the gate.
+    fn raw_invert<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        f: FM,
+    ) -> Result<(Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>)>;
+
+    /// `n / d = q`. `f` returns `(n, d, q)` and the cells are returned in the same order.
+    ///
+    /// `q * d = n` alone is satisfied by any `q` when `n = d = 0`, so the gate
+    /// also proves that `d` has an inverse. Returns `Error::Synthesis` if `d`
+    /// is known to be zero.
+    fn raw_div<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        f: FM,
+    ) -> Result<(Cell, Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>;
 }
 
+// 各メソッドはTutorialBuilderで1行だけのregionを作る。
+// 何行もまとめて置きたいときはassign_packedを直接使う。
 impl<F: FieldExt> TutorialComposer<F> for TutorialChip<F> {
     fn raw_multiply<FM>(
         &self,
@@ -87,39 +211,10 @@ impl<F: FieldExt> TutorialComposer<F> for TutorialChip<F> {
     where
         FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
     {
-        layouter.assign_region(
-            || "mul",
-            |mut region| {
-                let mut values = None;
-                let lhs = region.assign_advice(
-                    || "lhs",
-                    self.config.l,
-                    0,
-                    || {
-                        values = Some(f());
-                        values.unwrap().map(|v| v.0)
-                    },
-                )?;
-                let rhs = region.assign_advice(
-                    || "rhs",
-                    self.config.r,
-                    0,
-                    || values.unwrap().map(|v| v.1),
-                )?;
-
-                let out = region.assign_advice(
-                    || "out",
-                    self.config.o,
-                    0,
-                    || values.unwrap().map(|v| v.2),
-                )?;
-
-                region.assign_fixed(|| "m", self.config.sm, 0, || Value::known(F::one()))?;
-                region.assign_fixed(|| "o", self.config.so, 0, || Value::known(F::one()))?;
-
-                Ok((lhs.cell(), rhs.cell(), out.cell()))
-            },
-        )
+        self.assign_packed(layouter, "mul", |b| {
+            let (lhs, rhs, out) = b.raw_multiply(f())?;
+            Ok((lhs.cell(), rhs.cell(), out.cell()))
+        })
     }
 
     fn raw_add<FM>(
@@ -130,50 +225,14 @@ impl<F: FieldExt> TutorialComposer<F> for TutorialChip<F> {
     where
         FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
     {
-        layouter.assign_region(
-            || "mul",
-            |mut region| {
-                let mut values = None;
-                let lhs = region.assign_advice(
-                    || "lhs",
-                    self.config.l,
-                    0,
-                    || {
-                        values = Some(f());
-                        values.unwrap().map(|v| v.0)
-                    },
-                )?;
-                let rhs = region.assign_advice(
-                    || "rhs",
-                    self.config.r,
-                    0,
-                    || values.unwrap().map(|v| v.1),
-                )?;
-
-                let out = region.assign_advice(
-                    || "out",
-                    self.config.o,
-                    0,
-                    || values.unwrap().map(|v| v.2),
-                )?;
-
-                region.assign_fixed(|| "l", self.config.sl, 0, || Value::known(F::one()))?;
-                region.assign_fixed(|| "r", self.config.sr, 0, || Value::known(F::one()))?;
-                region.assign_fixed(|| "o", self.config.so, 0, || Value::known(F::one()))?;
-
-                Ok((lhs.cell(), rhs.cell(), out.cell()))
-            },
-        )
+        self.assign_packed(layouter, "add", |b| {
+            let (lhs, rhs, out) = b.raw_add(f())?;
+            Ok((lhs.cell(), rhs.cell(), out.cell()))
+        })
     }
 
     fn copy(&self, layouter: &mut impl Layouter<F>, left: Cell, right: Cell) -> Result<(), Error> {
-        layouter.assign_region(
-            || "copy",
-            |mut region| {
-                region.constrain_equal(left, right)?;
-                region.constrain_equal(left, right)
-            },
-        )
+        self.assign_packed(layouter, "copy", |b| b.copy(left, right))
     }
 
     fn expose_public(
@@ -184,15 +243,106 @@ impl<F: FieldExt> TutorialComposer<F> for TutorialChip<F> {
     ) -> Result<(), Error> {
         layouter.constrain_instance(cell, self.config.PI, row)
     }
+
+    fn raw_add_constant<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        constant: F,
+        mut f: FM,
+    ) -> Result<(Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>)>,
+    {
+        self.assign_packed(layouter, "add constant", |b| {
+            let (lhs, out) = b.raw_add_constant(constant, f())?;
+            Ok((lhs.cell(), out.cell()))
+        })
+    }
+
+    fn raw_mul_constant<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        constant: F,
+        mut f: FM,
+    ) -> Result<(Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>)>,
+    {
+        self.assign_packed(layouter, "mul constant", |b| {
+            let (lhs, out) = b.raw_mul_constant(constant, f())?;
+            Ok((lhs.cell(), out.cell()))
+        })
+    }
+
+    fn assert_constant(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cell: Cell,
+        constant: F,
+    ) -> Result<(), Error> {
+        self.assign_packed(layouter, "assert constant", |b| {
+            b.assert_constant(cell, constant)
+        })
+    }
+
+    fn raw_sub<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        mut f: FM,
+    ) -> Result<(Cell, Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
+    {
+        self.assign_packed(layouter, "sub", |b| {
+            let (lhs, rhs, out) = b.raw_sub(f())?;
+            Ok((lhs.cell(), rhs.cell(), out.cell()))
+        })
+    }
+
+    fn raw_invert<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        mut f: FM,
+    ) -> Result<(Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>)>,
+    {
+        self.assign_packed(layouter, "invert", |b| {
+            let (lhs, inv) = b.raw_invert(f())?;
+            Ok((lhs.cell(), inv.cell()))
+        })
+    }
+
+    fn raw_div<FM>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        mut f: FM,
+    ) -> Result<(Cell, Cell, Cell), Error>
+    where
+        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
+    {
+        self.assign_packed(layouter, "div", |b| {
+            let (numerator, denominator, quotient) = b.raw_div(f())?;
+            Ok((numerator.cell(), denominator.cell(), quotient.cell()))
+        })
+    }
 }
 
 #[derive(Default)]
-struct TutorialCircuit<F: FieldExt> {
+pub struct TutorialCircuit<F: FieldExt> {
     x: Value<F>,
     y: Value<F>,
     constant: F,
 }
 
+impl<F: FieldExt> TutorialCircuit<F> {
+    /// Proves knowledge of `x` and `y` such that `x^2 * y^2 + constant` equals the
+    /// second public input. The public inputs are `[constant, x^2 * y^2 + constant]`.
+    pub fn new(x: Value<F>, y: Value<F>, constant: F) -> Self {
+        Self { x, y, constant }
+    }
+}
+
 impl<F: FieldExt> Circuit<F> for TutorialCircuit<F> {
     type Config = TutorialConfig;
     type FloorPlanner = SimpleFloorPlanner;
@@ -202,51 +352,83 @@ impl<F: FieldExt> Circuit<F> for TutorialCircuit<F> {
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let l = meta.advice_column();
-        let r = meta.advice_column();
-        let o = meta.advice_column();
+        TutorialChip::configure(meta)
+    }
 
-        meta.enable_equality(l);
-        meta.enable_equality(r);
-        meta.enable_equality(o);
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let cs = TutorialChip::new(config);
 
-        let sm = meta.fixed_column();
-        let sl = meta.fixed_column();
-        let sr = meta.fixed_column();
-        let so = meta.fixed_column();
-        let sc = meta.fixed_column();
-        
-        #[allow(non_snake_case)]
-        let PI = meta.instance_column();
-        meta.enable_equality(PI);
+        // TutorialCellは値も持っているので、x^2などのwitnessを手で計算し直さなくていい。
+        // 入力のcopyもmul/addの中でやってくれる。
+        let (consty, out) = cs.assign_packed(&mut layouter, "x^2 * y^2 + c", |b| {
+            let x = b.load_private(self.x)?;
+            let y = b.load_private(self.y)?;
+            let consty = b.load_private(Value::known(self.constant))?;
+
+            // Create x squared, y squared and xy squared
+            let x2 = b.mul(&x, &x)?;
+            let y2 = b.mul(&y, &y)?;
+            let x2y2 = b.mul(&x2, &y2)?;
+
+            // Add the constant
+            let out = b.add(&x2y2, &consty)?;
+            Ok((consty, out))
+        })?;
 
-        meta.create_gate("mini plonk", |meta| {
-            let l = meta.query_advice(l, Rotation::cur());
-            let r = meta.query_advice(r, Rotation::cur());
-            let o = meta.query_advice(o, Rotation::cur());
+        // Ensure that the constant in the TutorialCircuit struct is correctly used and that the
+        // result of the circuit computation is what is expected.
+        cs.expose_public(&mut layouter, consty.cell(), 0)?;
+        // Below is another way to expose a public value, this time the output value of the computation
+        // (Use constrain_instance)
+        layouter.constrain_instance(out.cell(), cs.config.PI, 1)?;
 
-            let sl = meta.query_fixed(sl, Rotation::cur());
-            let sr = meta.query_fixed(sr, Rotation::cur());
-            let so = meta.query_fixed(so, Rotation::cur());
-            let sm = meta.query_fixed(sm, Rotation::cur());
-            let sc = meta.query_fixed(sc, Rotation::cur());
+        Ok(())
+    }
+}
 
-            vec![l.clone() * sl + r.clone() * sr + l * r * sm + (o * so * (-F::one())) + sc]
-        });
+/// Same statement as `TutorialCircuit`, but `scale` and `constant` are baked into
+/// the fixed columns, so the only public input is `scale * x^2 * y^2 + constant`.
+#[derive(Default)]
+pub struct TutorialConstantCircuit<F: FieldExt> {
+    x: Value<F>,
+    y: Value<F>,
+    scale: F,
+    constant: F,
+}
 
-        TutorialConfig {
-            l,
-            r,
-            o,
-            sl,
-            sr,
-            so,
-            sm,
-            sc,
-            PI,
+impl<F: FieldExt> TutorialConstantCircuit<F> {
+    pub fn new(x: Value<F>, y: Value<F>, scale: F, constant: F) -> Self {
+        Self {
+            x,
+            y,
+            scale,
+            constant,
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for TutorialConstantCircuit<F> {
+    type Config = TutorialConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // 定数はfixed columnに入るので回路の形の一部。witnessだけ消す
+    fn without_witnesses(&self) -> Self {
+        Self {
+            x: Value::unknown(),
+            y: Value::unknown(),
+            scale: self.scale,
+            constant: self.constant,
         }
     }
 
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        TutorialChip::configure(meta)
+    }
+
     fn synthesize(
         &self,
         config: Self::Config,
@@ -254,44 +436,21 @@ impl<F: FieldExt> Circuit<F> for TutorialCircuit<F> {
     ) -> Result<(), Error> {
         let cs = TutorialChip::new(config);
 
-        // Initialise these values so that we can access them more easily outside the block we actually give them a value in
-        let x: Value<Assigned<_>> = self.x.into();
-        let y: Value<Assigned<_>> = self.y.into();
-        let consty = Assigned::from(self.constant);
-
-        // Create x squared
-        // Note that the variables named ai for some i are just place holders, meaning that a0 isn't
-        // necessarily the first entry in the column a; though in the code we try to make things clear
-        let (a0, b0, c0) = cs.raw_multiply(&mut layouter, || x.map(|x| (x, x, x * x)))?;
-        cs.copy(&mut layouter, a0, b0)?;
+        let out = cs.assign_packed(&mut layouter, "scale * x^2 * y^2 + c", |b| {
+            let x = b.load_private(self.x)?;
+            let y = b.load_private(self.y)?;
 
-        // Create y squared
-        let (a1, b1, c1) = cs.raw_multiply(&mut layouter, || y.map(|y| (y, y, y * y)))?;
-        cs.copy(&mut layouter, a1, b1)?;
+            // x^2, y^2, x^2 * y^2 are the same as in TutorialCircuit
+            let x2 = b.mul(&x, &x)?;
+            let y2 = b.mul(&y, &y)?;
+            let x2y2 = b.mul(&x2, &y2)?;
 
-        // Create xy squared
-        let (a2, b2, c2) = cs.raw_multiply(&mut layouter, || {
-            x.zip(y).map(|(x, y)| (x * x, y * y, x * x * y * y))
+            // Multiply by the fixed scale and add the fixed constant
+            let scaled = b.mul_constant(&x2y2, self.scale)?;
+            b.add_constant(&scaled, self.constant)
         })?;
-        cs.copy(&mut layouter, c0, a2)?;
-        cs.copy(&mut layouter, c1, b2)?;
 
-        // Add the constant
-        let (a3, b3, c3) = cs.raw_add(&mut layouter, || {
-            x.zip(y)
-                .map(|(x, y)| (x * x * y * y, consty, x * x * y * y + consty))
-        })?;
-        cs.copy(&mut layouter, c2, a3)?;
-
-        // Ensure that the constant in the TutorialCircuit struct is correctly used and that the
-        // result of the circuit computation is what is expected. (use expose_public))
-        // cs.expose_public(&mut layouter, b3, 0)?;
-        layouter.constrain_instance(b3, cs.config.PI, 0)?;
-        // Below is another way to expose a public value, this time the output value of the computation
-        // (Use constrain_instance)
-        layouter.constrain_instance(c3, cs.config.PI, 1)?;
-        // 次のコードでも良い
-        // cs.expose_public(&mut layouter, c3, 1)?;
+        cs.expose_public(&mut layouter, out.cell(), 0)?;
 
         Ok(())
     }
@@ -325,9 +484,236 @@ fn e0_tutorial_answer_test() {
     let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
     assert_eq!(prover.verify(), Ok(()));
 
-    // TODO: This broke when Value was introduced to replace Option. Fix it
     // If we try some other public input, the proof will fail!
     public_inputs[0] += Fp::one();
-    // let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
-    // assert!(prover.verify().is_err());
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn e0_tutorial_answer_prove_verify_test() {
+    use crate::prover::{prove, setup, verify};
+    use halo2_proofs::pasta::Fp;
+
+    let k = 4;
+
+    let constant = Fp::from(7);
+    let x = Fp::from(5);
+    let y = Fp::from(9);
+    let z = Fp::from(25 * 81 + 7);
+
+    let circuit: TutorialCircuit<Fp> = TutorialCircuit {
+        x: Value::known(x),
+        y: Value::known(y),
+        constant,
+    };
+
+    let public_inputs = vec![constant, z];
+
+    let (params, pk) = setup(k, &circuit).unwrap();
+    let proof = prove(&params, &pk, circuit, &[&public_inputs]).unwrap();
+
+    // 同じpublic inputsなら本物のverifierも通る
+    assert!(verify(&params, pk.get_vk(), &proof, &[&public_inputs]).is_ok());
+
+    // public inputsを変えるとverifyに失敗する
+    let wrong_inputs = vec![constant, z + Fp::one()];
+    assert!(verify(&params, pk.get_vk(), &proof, &[&wrong_inputs]).is_err());
+}
+
+#[test]
+fn e0_tutorial_answer_soundness_test() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::pasta::Fp;
+
+    let constant = Fp::from(7);
+    let circuit = TutorialCircuit::new(
+        Value::known(Fp::from(5)),
+        Value::known(Fp::from(9)),
+        constant,
+    );
+
+    // public inputもadviceのcellも、1つでも変えたらverifyに失敗するはず
+    assert_sound(4, &circuit, vec![vec![constant, Fp::from(25 * 81 + 7)]]);
+}
+
+#[test]
+fn e0_tutorial_constant_test() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let k = 4;
+    let scale = Fp::from(3);
+    let constant = Fp::from(7);
+    let z = Fp::from(3 * 25 * 81 + 7);
+
+    let circuit = TutorialConstantCircuit::new(
+        Value::known(Fp::from(5)),
+        Value::known(Fp::from(9)),
+        scale,
+        constant,
+    );
+
+    // constantはpublic inputではないので、zだけを渡す
+    let prover = MockProver::run(k, &circuit, vec![vec![z]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // A circuit with another fixed constant does not accept the same output
+    let other = TutorialConstantCircuit::new(
+        Value::known(Fp::from(5)),
+        Value::known(Fp::from(9)),
+        scale,
+        constant + Fp::one(),
+    );
+    let prover = MockProver::run(k, &other, vec![vec![z]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    assert_sound(k, &circuit, vec![vec![z]]);
+}
+
+#[test]
+fn e0_tutorial_assert_constant_test() {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // x * x == 25 を、25をpublicにせずに確認する
+    #[derive(Default)]
+    struct SquareIs25<F: FieldExt> {
+        x: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for SquareIs25<F> {
+        type Config = TutorialConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            TutorialChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let cs = TutorialChip::new(config);
+            let x: Value<Assigned<_>> = self.x.into();
+
+            let (a0, b0, c0) = cs.raw_multiply(&mut layouter, || x.map(|x| (x, x, x * x)))?;
+            cs.copy(&mut layouter, a0, b0)?;
+            cs.assert_constant(&mut layouter, c0, F::from(25u64))
+        }
+    }
+
+    let k = 4;
+
+    let circuit = SquareIs25 {
+        x: Value::known(Fp::from(5)),
+    };
+    let prover = MockProver::run(k, &circuit, vec![vec![]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let circuit = SquareIs25 {
+        x: Value::known(Fp::from(4)),
+    };
+    let prover = MockProver::run(k, &circuit, vec![vec![]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn e0_tutorial_division_test() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{arithmetic::Field, dev::MockProver, pasta::Fp};
+
+    // (x - y) / z と 1 / z をpublicにする
+    #[derive(Default)]
+    struct Division<F: FieldExt> {
+        x: Value<F>,
+        y: Value<F>,
+        z: Value<F>,
+        // 間違った商も試せるように、商は外から渡す
+        quotient: Value<Assigned<F>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for Division<F> {
+        type Config = TutorialConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            TutorialChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let cs = TutorialChip::new(config);
+            let x: Value<Assigned<_>> = self.x.into();
+            let y: Value<Assigned<_>> = self.y.into();
+            let z: Value<Assigned<_>> = self.z.into();
+            let quotient = self.quotient;
+
+            let (_, _, diff) =
+                cs.raw_sub(&mut layouter, || x.zip(y).map(|(x, y)| (x, y, x - y)))?;
+
+            let (n, d, q) = cs.raw_div(&mut layouter, || {
+                x.zip(y)
+                    .zip(z)
+                    .zip(quotient)
+                    .map(|(((x, y), z), q)| (x - y, z, q))
+            })?;
+            cs.copy(&mut layouter, diff, n)?;
+
+            let (z_cell, inv) = cs.raw_invert(&mut layouter, || z.map(|z| (z, z.invert())))?;
+            cs.copy(&mut layouter, d, z_cell)?;
+
+            cs.expose_public(&mut layouter, q, 0)?;
+            cs.expose_public(&mut layouter, inv, 1)
+        }
+    }
+
+    let k = 4;
+    let division = |x: u64, y: u64, z: u64, quotient: Assigned<Fp>| Division {
+        x: Value::known(Fp::from(x)),
+        y: Value::known(Fp::from(y)),
+        z: Value::known(Fp::from(z)),
+        quotient: Value::known(quotient),
+    };
+    let third = Fp::from(3).invert().unwrap();
+    let half = Fp::from(2).invert().unwrap();
+
+    // (10 - 4) / 3 = 2
+    let circuit = division(10, 4, 3, Assigned::from(Fp::from(2)));
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(2), third]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    assert_sound(k, &circuit, vec![vec![Fp::from(2), third]]);
+
+    // 割り切れなくても、Assignedの有理数のまま渡せる: (7 - 0) / 2
+    let circuit = division(
+        7,
+        0,
+        2,
+        Assigned::from(Fp::from(7)) * Assigned::from(Fp::from(2)).invert(),
+    );
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(7) * half, half]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // 間違った商は q * d = n を満たさない
+    let circuit = division(10, 4, 3, Assigned::from(Fp::from(3)));
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(3), third]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 0で割ろうとすると、制約を作る前にエラーになる
+    let circuit = division(0, 0, 0, Assigned::from(Fp::from(5)));
+    assert!(matches!(
+        MockProver::run(k, &circuit, vec![vec![Fp::from(5), Fp::zero()]]),
+        Err(Error::Synthesis)
+    ));
 }
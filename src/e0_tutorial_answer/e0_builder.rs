@@ -0,0 +1,443 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Cell, Layouter, Region, Value},
+    plonk::{Advice, Assigned, Column, Error, Fixed},
+};
+
+use super::{TutorialChip, TutorialConfig};
+
+// TutorialComposerのメソッドは1つのgateごとにassign_regionしていたが、
+// TutorialBuilderは1つのregionの中で行カウンタを進めながらgateを並べていく。
+//
+//   row | l | r | o | sl | sr | so | sm | sc
+//   ----+---+---+---+----+----+----+----+----
+//    0  | x | x | x2|    |    |  1 |  1 |      <- raw_multiply
+//    1  | x2| c |...|  1 |  1 |  1 |    |      <- raw_add
+//   ...
+//
+// copyは同じregionの中でconstrain_equalするだけなので、空のregionは作らない。
+// 割り当てたcellはAssignedCellで返すので、値もあとから取り出せる。
+
+/// A cell of the TutorialChip, together with its value.
+pub type TutorialCell<F> = AssignedCell<Assigned<F>, F>;
+
+/// Lays out TutorialChip gates one per row in a single region.
+///
+/// Created by [`TutorialChip::assign_packed`].
+pub struct TutorialBuilder<'r, 'c, F: FieldExt> {
+    region: Region<'r, F>,
+    config: &'c TutorialConfig,
+    offset: usize,
+    // load_privateで使っている行と、次に空いているcolumn (l, r, oの順)
+    free_slot: Option<(usize, usize)>,
+}
+
+impl<F: FieldExt> TutorialChip<F> {
+    /// Runs `f` with a builder that places every gate in the same region.
+    pub fn assign_packed<T, B>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        name: &str,
+        mut f: B,
+    ) -> Result<T, Error>
+    where
+        B: FnMut(&mut TutorialBuilder<'_, '_, F>) -> Result<T, Error>,
+    {
+        layouter.assign_region(
+            || name,
+            |region| {
+                let mut builder = TutorialBuilder {
+                    region,
+                    config: &self.config,
+                    offset: 0,
+                    free_slot: None,
+                };
+                f(&mut builder)
+            },
+        )
+    }
+}
+
+impl<'r, 'c, F: FieldExt> TutorialBuilder<'r, 'c, F> {
+    /// Number of rows used so far.
+    pub fn rows(&self) -> usize {
+        self.offset
+    }
+
+    fn advice(
+        &mut self,
+        name: &str,
+        column: Column<Advice>,
+        value: Value<Assigned<F>>,
+    ) -> Result<TutorialCell<F>, Error> {
+        self.region
+            .assign_advice(|| name, column, self.offset, || value)
+    }
+
+    fn fixed(&mut self, name: &str, column: Column<Fixed>, value: F) -> Result<(), Error> {
+        self.region
+            .assign_fixed(|| name, column, self.offset, || Value::known(value))?;
+        Ok(())
+    }
+
+    fn next_row(&mut self) {
+        self.offset += 1;
+    }
+
+    /// `l * r = o`
+    pub fn raw_multiply(
+        &mut self,
+        values: Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
+    ) -> Result<(TutorialCell<F>, TutorialCell<F>, TutorialCell<F>), Error> {
+        let config = self.config;
+        let lhs = self.advice("lhs", config.l, values.map(|v| v.0))?;
+        let rhs = self.advice("rhs", config.r, values.map(|v| v.1))?;
+        let out = self.advice("out", config.o, values.map(|v| v.2))?;
+
+        self.fixed("m", config.sm, F::one())?;
+        self.fixed("o", config.so, F::one())?;
+        self.next_row();
+
+        Ok((lhs, rhs, out))
+    }
+
+    /// `l + r = o`
+    pub fn raw_add(
+        &mut self,
+        values: Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
+    ) -> Result<(TutorialCell<F>, TutorialCell<F>, TutorialCell<F>), Error> {
+        let config = self.config;
+        let lhs = self.advice("lhs", config.l, values.map(|v| v.0))?;
+        let rhs = self.advice("rhs", config.r, values.map(|v| v.1))?;
+        let out = self.advice("out", config.o, values.map(|v| v.2))?;
+
+        self.fixed("l", config.sl, F::one())?;
+        self.fixed("r", config.sr, F::one())?;
+        self.fixed("o", config.so, F::one())?;
+        self.next_row();
+
+        Ok((lhs, rhs, out))
+    }
+
+    /// `l - r = o`
+    pub fn raw_sub(
+        &mut self,
+        values: Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
+    ) -> Result<(TutorialCell<F>, TutorialCell<F>, TutorialCell<F>), Error> {
+        let config = self.config;
+        let lhs = self.advice("lhs", config.l, values.map(|v| v.0))?;
+        let rhs = self.advice("rhs", config.r, values.map(|v| v.1))?;
+        let out = self.advice("out", config.o, values.map(|v| v.2))?;
+
+        // l * 1 + r * (-1) - o * 1 = 0
+        self.fixed("l", config.sl, F::one())?;
+        self.fixed("r", config.sr, -F::one())?;
+        self.fixed("o", config.so, F::one())?;
+        self.next_row();
+
+        Ok((lhs, rhs, out))
+    }
+
+    /// `l + constant = o`, with the constant baked into the `sc` column.
+    pub fn raw_add_constant(
+        &mut self,
+        constant: F,
+        values: Value<(Assigned<F>, Assigned<F>)>,
+    ) -> Result<(TutorialCell<F>, TutorialCell<F>), Error> {
+        let config = self.config;
+        let lhs = self.advice("lhs", config.l, values.map(|v| v.0))?;
+        let out = self.advice("out", config.o, values.map(|v| v.1))?;
+
+        // l * 1 - o * 1 + c = 0
+        self.fixed("l", config.sl, F::one())?;
+        self.fixed("o", config.so, F::one())?;
+        self.fixed("c", config.sc, constant)?;
+        self.next_row();
+
+        Ok((lhs, out))
+    }
+
+    /// `l * constant = o`, with the constant baked into the `sl` column.
+    pub fn raw_mul_constant(
+        &mut self,
+        constant: F,
+        values: Value<(Assigned<F>, Assigned<F>)>,
+    ) -> Result<(TutorialCell<F>, TutorialCell<F>), Error> {
+        let config = self.config;
+        let lhs = self.advice("lhs", config.l, values.map(|v| v.0))?;
+        let out = self.advice("out", config.o, values.map(|v| v.1))?;
+
+        // l * c - o * 1 = 0
+        self.fixed("l", config.sl, constant)?;
+        self.fixed("o", config.so, F::one())?;
+        self.next_row();
+
+        Ok((lhs, out))
+    }
+
+    /// `l * r = 1`. Returns `Error::Synthesis` if `l` is known to be zero.
+    pub fn raw_invert(
+        &mut self,
+        values: Value<(Assigned<F>, Assigned<F>)>,
+    ) -> Result<(TutorialCell<F>, TutorialCell<F>), Error> {
+        values.error_if_known_and(|v| v.0.is_zero_vartime())?;
+
+        let config = self.config;
+        let lhs = self.advice("lhs", config.l, values.map(|v| v.0))?;
+        let inv = self.advice("inv", config.r, values.map(|v| v.1))?;
+
+        // l * r * 1 - 1 = 0
+        self.fixed("m", config.sm, F::one())?;
+        self.fixed("c", config.sc, -F::one())?;
+        self.next_row();
+
+        Ok((lhs, inv))
+    }
+
+    /// `n / d = q` over two rows, `q * d = n` and `d * d^-1 = 1`.
+    /// Returns `Error::Synthesis` if `d` is known to be zero.
+    pub fn raw_div(
+        &mut self,
+        values: Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
+    ) -> Result<(TutorialCell<F>, TutorialCell<F>, TutorialCell<F>), Error> {
+        let (quotient, denominator, numerator) =
+            self.raw_multiply(values.map(|(n, d, q)| (q, d, n)))?;
+        let (d, _) = self.raw_invert(values.map(|(_, d, _)| (d, d.invert())))?;
+        self.copy(denominator.cell(), d.cell())?;
+
+        Ok((numerator, denominator, quotient))
+    }
+
+    /// Ensure a wire holds `constant`, without making the constant a public input.
+    pub fn assert_constant(&mut self, cell: Cell, constant: F) -> Result<(), Error> {
+        // 正しいwitnessならcellの値はconstantなので、そのまま割り当ててcopyでつなぐ
+        let lhs = self.load_constant(constant)?;
+        self.copy(cell, lhs.cell())
+    }
+
+    /// Ensure two wire values are the same, in effect connecting the wires to each other
+    pub fn copy(&mut self, left: Cell, right: Cell) -> Result<(), Error> {
+        self.region.constrain_equal(left, right)
+    }
+
+    // ここから下はTutorialCellを受け取って返す版。
+    // 入力はcopyで新しい行に持ってきて、出力のwitnessは入力の値から計算するので、
+    // 呼び出し側で x.zip(y).map(...) のように値を組み立て直さなくていい。
+
+    /// Witnesses a private value. Up to three values share one row, since the
+    /// row's selectors are all zero.
+    pub fn load_private(&mut self, value: Value<F>) -> Result<TutorialCell<F>, Error> {
+        let config = self.config;
+        let (row, slot) = match self.free_slot.take() {
+            Some(free) => free,
+            None => {
+                let row = self.offset;
+                self.next_row();
+                (row, 0)
+            }
+        };
+        if slot < 2 {
+            self.free_slot = Some((row, slot + 1));
+        }
+
+        let column = [config.l, config.r, config.o][slot];
+        let value: Value<Assigned<F>> = value.into();
+        self.region
+            .assign_advice(|| "private", column, row, || value)
+    }
+
+    /// A cell fixed to `constant` by the `sc` column.
+    pub fn load_constant(&mut self, constant: F) -> Result<TutorialCell<F>, Error> {
+        let config = self.config;
+        let cell = self.advice("constant", config.l, Value::known(Assigned::from(constant)))?;
+
+        // l * 1 - c = 0
+        self.fixed("l", config.sl, F::one())?;
+        self.fixed("c", config.sc, -constant)?;
+        self.next_row();
+
+        Ok(cell)
+    }
+
+    /// `a * b`
+    pub fn mul(
+        &mut self,
+        a: &TutorialCell<F>,
+        b: &TutorialCell<F>,
+    ) -> Result<TutorialCell<F>, Error> {
+        let values = a.value().zip(b.value()).map(|(a, b)| (*a, *b, *a * *b));
+        let (lhs, rhs, out) = self.raw_multiply(values)?;
+        self.copy(a.cell(), lhs.cell())?;
+        self.copy(b.cell(), rhs.cell())?;
+
+        Ok(out)
+    }
+
+    /// `a + b`
+    pub fn add(
+        &mut self,
+        a: &TutorialCell<F>,
+        b: &TutorialCell<F>,
+    ) -> Result<TutorialCell<F>, Error> {
+        let values = a.value().zip(b.value()).map(|(a, b)| (*a, *b, *a + *b));
+        let (lhs, rhs, out) = self.raw_add(values)?;
+        self.copy(a.cell(), lhs.cell())?;
+        self.copy(b.cell(), rhs.cell())?;
+
+        Ok(out)
+    }
+
+    /// `a - b`
+    pub fn sub(
+        &mut self,
+        a: &TutorialCell<F>,
+        b: &TutorialCell<F>,
+    ) -> Result<TutorialCell<F>, Error> {
+        let values = a.value().zip(b.value()).map(|(a, b)| (*a, *b, *a - *b));
+        let (lhs, rhs, out) = self.raw_sub(values)?;
+        self.copy(a.cell(), lhs.cell())?;
+        self.copy(b.cell(), rhs.cell())?;
+
+        Ok(out)
+    }
+
+    /// `a / b`. Returns `Error::Synthesis` if `b` is known to be zero.
+    pub fn div(
+        &mut self,
+        a: &TutorialCell<F>,
+        b: &TutorialCell<F>,
+    ) -> Result<TutorialCell<F>, Error> {
+        let values = a
+            .value()
+            .zip(b.value())
+            .map(|(a, b)| (*a, *b, *a * b.invert()));
+        let (numerator, denominator, quotient) = self.raw_div(values)?;
+        self.copy(a.cell(), numerator.cell())?;
+        self.copy(b.cell(), denominator.cell())?;
+
+        Ok(quotient)
+    }
+
+    /// `1 / a`. Returns `Error::Synthesis` if `a` is known to be zero.
+    pub fn invert(&mut self, a: &TutorialCell<F>) -> Result<TutorialCell<F>, Error> {
+        let (lhs, inv) = self.raw_invert(a.value().map(|a| (*a, a.invert())))?;
+        self.copy(a.cell(), lhs.cell())?;
+
+        Ok(inv)
+    }
+
+    /// `a + constant`
+    pub fn add_constant(
+        &mut self,
+        a: &TutorialCell<F>,
+        constant: F,
+    ) -> Result<TutorialCell<F>, Error> {
+        let c = Assigned::from(constant);
+        let (lhs, out) = self.raw_add_constant(constant, a.value().map(|a| (*a, *a + c)))?;
+        self.copy(a.cell(), lhs.cell())?;
+
+        Ok(out)
+    }
+
+    /// `a * constant`
+    pub fn mul_constant(
+        &mut self,
+        a: &TutorialCell<F>,
+        constant: F,
+    ) -> Result<TutorialCell<F>, Error> {
+        let c = Assigned::from(constant);
+        let (lhs, out) = self.raw_mul_constant(constant, a.value().map(|a| (*a, *a * c)))?;
+        self.copy(a.cell(), lhs.cell())?;
+
+        Ok(out)
+    }
+}
+
+#[test]
+fn e0_builder_test() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem},
+    };
+
+    use super::TutorialComposer;
+
+    // TutorialCircuitと同じ x^2 * y^2 + c を1つのregionで組む
+    #[derive(Default)]
+    struct Packed<F: FieldExt> {
+        x: Value<F>,
+        y: Value<F>,
+        constant: F,
+    }
+
+    impl<F: FieldExt> Circuit<F> for Packed<F> {
+        type Config = TutorialConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                constant: self.constant,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            TutorialChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let cs = TutorialChip::new(config);
+            let x: Value<Assigned<_>> = self.x.into();
+            let y: Value<Assigned<_>> = self.y.into();
+            let c = Assigned::from(self.constant);
+
+            let out = cs.assign_packed(&mut layouter, "x^2 * y^2 + c", |b| {
+                let (a0, b0, c0) = b.raw_multiply(x.map(|x| (x, x, x * x)))?;
+                b.copy(a0.cell(), b0.cell())?;
+
+                let (a1, b1, c1) = b.raw_multiply(y.map(|y| (y, y, y * y)))?;
+                b.copy(a1.cell(), b1.cell())?;
+
+                let (a2, b2, c2) = b.raw_multiply(
+                    c0.value()
+                        .zip(c1.value())
+                        .map(|(x2, y2)| (*x2, *y2, *x2 * *y2)),
+                )?;
+                b.copy(c0.cell(), a2.cell())?;
+                b.copy(c1.cell(), b2.cell())?;
+
+                let (a3, out) =
+                    b.raw_add_constant(self.constant, c2.value().map(|v| (*v, *v + c)))?;
+                b.copy(c2.cell(), a3.cell())?;
+
+                assert_eq!(b.rows(), 4);
+                Ok(out)
+            })?;
+
+            cs.expose_public(&mut layouter, out.cell(), 0)
+        }
+    }
+
+    let k = 4;
+    let circuit = Packed {
+        x: Value::known(Fp::from(5)),
+        y: Value::known(Fp::from(9)),
+        constant: Fp::from(7),
+    };
+    let z = Fp::from(25 * 81 + 7);
+
+    let prover = MockProver::run(k, &circuit, vec![vec![z]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let prover = MockProver::run(k, &circuit, vec![vec![z + Fp::one()]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    assert_sound(k, &circuit, vec![vec![z]]);
+}
@@ -0,0 +1,672 @@
+use std::{collections::BTreeMap, fmt, ops, str::FromStr};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Cell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Assigned, Circuit, ConstraintSystem, Error},
+};
+
+use super::{TutorialChip, TutorialComposer, TutorialConfig};
+
+// x^3 + x + 5 みたいな式を書くと、raw_multiply / raw_add / 定数gateとcopyに落としてくれる。
+//
+//   "x^3 + x + 5"  --parse-->  Expr  --ExpressionCompiler-->  TutorialComposerのgate
+//
+// 定数同士の演算はコンパイル時に畳み込んで、変数との演算はraw_add_constant/raw_mul_constantで
+// fixed columnに入れる。同じ変数が何回出てきても、最初に置いたcellにcopyでつなぐ。
+
+/// An arithmetic expression over named variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Var(String),
+    Constant(u64),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, u32),
+}
+
+impl Expr {
+    pub fn var(name: &str) -> Self {
+        Expr::Var(name.to_string())
+    }
+
+    pub fn constant(value: u64) -> Self {
+        Expr::Constant(value)
+    }
+
+    pub fn pow(self, exp: u32) -> Self {
+        Expr::Pow(Box::new(self), exp)
+    }
+
+    /// Evaluates the expression outside of the circuit, e.g. to compute the
+    /// public outputs. Returns `None` if a variable is missing.
+    pub fn evaluate<F: FieldExt>(&self, vars: &BTreeMap<String, F>) -> Option<F> {
+        Some(match self {
+            Expr::Var(name) => *vars.get(name)?,
+            Expr::Constant(c) => F::from(*c),
+            Expr::Neg(e) => -e.evaluate(vars)?,
+            Expr::Add(a, b) => a.evaluate(vars)? + b.evaluate(vars)?,
+            Expr::Sub(a, b) => a.evaluate(vars)? - b.evaluate(vars)?,
+            Expr::Mul(a, b) => a.evaluate(vars)? * b.evaluate(vars)?,
+            Expr::Pow(e, exp) => e.evaluate(vars)?.pow_vartime(&[*exp as u64]),
+        })
+    }
+}
+
+impl ops::Add for Expr {
+    type Output = Expr;
+
+    fn add(self, rhs: Expr) -> Expr {
+        Expr::Add(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl ops::Sub for Expr {
+    type Output = Expr;
+
+    fn sub(self, rhs: Expr) -> Expr {
+        Expr::Sub(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl ops::Mul for Expr {
+    type Output = Expr;
+
+    fn mul(self, rhs: Expr) -> Expr {
+        Expr::Mul(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl ops::Neg for Expr {
+    type Output = Expr;
+
+    fn neg(self) -> Expr {
+        Expr::Neg(Box::new(self))
+    }
+}
+
+/// Where and why parsing an expression failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte offset into the input.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    /// Parses `+`, `-`, `*`, `^` (with a literal exponent), parentheses,
+    /// decimal constants and identifiers, with the usual precedence.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: s.char_indices().collect(),
+            pos: 0,
+            end: s.len(),
+        };
+
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some((position, c)) => Err(ParseError {
+                position,
+                message: format!("unexpected `{}`", c),
+            }),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, char)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    // 空白は区切りとして残しておく。"x y" を "xy" と読まないように、
+    // 識別子と数字は空白を飛ばさずにcurrentで読む。
+    fn peek(&self) -> Option<(usize, char)> {
+        self.tokens[self.pos..]
+            .iter()
+            .find(|(_, c)| !c.is_whitespace())
+            .copied()
+    }
+
+    fn current(&self) -> Option<(usize, char)> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.current(), Some((_, c)) if c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    // peekした文字を読む
+    fn bump(&mut self) {
+        self.skip_whitespace();
+        self.pos += 1;
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, ParseError> {
+        Err(ParseError {
+            position: self.peek().map_or(self.end, |(position, _)| position),
+            message: message.to_string(),
+        })
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.term()?;
+        while let Some((_, op @ ('+' | '-'))) = self.peek() {
+            self.bump();
+            let rhs = self.term()?;
+            lhs = if op == '+' { lhs + rhs } else { lhs - rhs };
+        }
+        Ok(lhs)
+    }
+
+    // term := unary ('*' unary)*
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.unary()?;
+        while let Some((_, '*')) = self.peek() {
+            self.bump();
+            lhs = lhs * self.unary()?;
+        }
+        Ok(lhs)
+    }
+
+    // unary := '-' unary | power
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if let Some((_, '-')) = self.peek() {
+            self.bump();
+            return Ok(-self.unary()?);
+        }
+        self.power()
+    }
+
+    // power := atom ('^' number)?
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.atom()?;
+        if let Some((_, '^')) = self.peek() {
+            self.bump();
+            let exp = self.number()?;
+            let exp = u32::try_from(exp).or_else(|_| self.error("exponent too large"))?;
+            return Ok(base.pow(exp));
+        }
+        Ok(base)
+    }
+
+    // atom := number | identifier | '(' expr ')'
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some((_, '(')) => {
+                self.bump();
+                let expr = self.expr()?;
+                match self.peek() {
+                    Some((_, ')')) => {
+                        self.bump();
+                        Ok(expr)
+                    }
+                    _ => self.error("expected `)`"),
+                }
+            }
+            Some((_, c)) if c.is_ascii_digit() => Ok(Expr::Constant(self.number()?)),
+            Some((_, c)) if c.is_alphabetic() || c == '_' => {
+                self.skip_whitespace();
+                let mut name = String::new();
+                while let Some((_, c)) = self.current() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    name.push(c);
+                    self.pos += 1;
+                }
+                Ok(Expr::Var(name))
+            }
+            _ => self.error("expected a number, a variable or `(`"),
+        }
+    }
+
+    fn number(&mut self) -> Result<u64, ParseError> {
+        self.skip_whitespace();
+        let mut value: Option<u64> = None;
+        while let Some((_, c)) = self.current() {
+            let digit = match c.to_digit(10) {
+                Some(digit) => digit as u64,
+                None => break,
+            };
+            value = match value.unwrap_or(0).checked_mul(10).and_then(|v| v.checked_add(digit)) {
+                Some(v) => Some(v),
+                None => return self.error("number does not fit in u64"),
+            };
+            self.pos += 1;
+        }
+        match value {
+            Some(value) => Ok(value),
+            None => self.error("expected a number"),
+        }
+    }
+}
+
+/// A value that has been placed in a cell of the TutorialChip.
+#[derive(Debug, Clone)]
+pub struct Wire<F: FieldExt> {
+    pub cell: Cell,
+    pub value: Value<Assigned<F>>,
+}
+
+/// An operand while lowering: constants stay symbolic until they meet a wire.
+#[derive(Clone)]
+pub(super) enum Operand<F: FieldExt> {
+    Constant(F),
+    Var(String),
+    Wire(Wire<F>),
+}
+
+/// Lowers `Expr`s to TutorialComposer gates.
+///
+/// Variables are shared across every expression compiled with the same
+/// compiler, so `x` in two outputs is the same wire. Each gate and copy goes
+/// through [`TutorialComposer`], i.e. gets a region of its own.
+pub struct ExpressionCompiler<'a, F: FieldExt> {
+    chip: &'a TutorialChip<F>,
+    vars: BTreeMap<String, (Option<Cell>, Value<Assigned<F>>)>,
+}
+
+impl<'a, F: FieldExt> ExpressionCompiler<'a, F> {
+    pub fn new(chip: &'a TutorialChip<F>) -> Self {
+        Self {
+            chip,
+            vars: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the witness of a variable. Variables without a witness are unknown.
+    pub fn witness(&mut self, name: &str, value: Value<F>) {
+        self.vars.insert(name.to_string(), (None, value.into()));
+    }
+
+    /// Lays out the gates computing `expr` and returns the wire holding the result.
+    pub fn compile(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        expr: &Expr,
+    ) -> Result<Wire<F>, Error> {
+        let operand = self.lower(layouter, expr)?;
+        self.place(layouter, operand)
+    }
+
+    fn lower(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        expr: &Expr,
+    ) -> Result<Operand<F>, Error> {
+        match expr {
+            Expr::Var(name) => Ok(Operand::Var(name.clone())),
+            Expr::Constant(c) => Ok(Operand::Constant(F::from(*c))),
+            Expr::Neg(e) => {
+                let e = self.lower(layouter, e)?;
+                self.mul(layouter, e, Operand::Constant(-F::one()))
+            }
+            Expr::Add(a, b) => {
+                let a = self.lower(layouter, a)?;
+                let b = self.lower(layouter, b)?;
+                self.add(layouter, a, b)
+            }
+            Expr::Sub(a, b) => {
+                let a = self.lower(layouter, a)?;
+                let b = self.lower(layouter, b)?;
+                let b = self.mul(layouter, b, Operand::Constant(-F::one()))?;
+                self.add(layouter, a, b)
+            }
+            Expr::Mul(a, b) => {
+                let a = self.lower(layouter, a)?;
+                let b = self.lower(layouter, b)?;
+                self.mul(layouter, a, b)
+            }
+            Expr::Pow(e, exp) => {
+                let base = self.lower(layouter, e)?;
+                self.pow(layouter, base, *exp)
+            }
+        }
+    }
+
+    fn value(&self, operand: &Operand<F>) -> Value<Assigned<F>> {
+        match operand {
+            Operand::Constant(c) => Value::known(Assigned::from(*c)),
+            Operand::Var(name) => self
+                .vars
+                .get(name)
+                .map_or_else(Value::unknown, |(_, value)| *value),
+            Operand::Wire(wire) => wire.value,
+        }
+    }
+
+    /// Connects `operand` to the input `cell` of a gate that was just laid out.
+    fn bind(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        operand: &Operand<F>,
+        cell: Cell,
+    ) -> Result<(), Error> {
+        let chip = self.chip;
+        match operand {
+            Operand::Constant(_) => unreachable!("constants are folded into the fixed columns"),
+            Operand::Var(name) => {
+                let var = self
+                    .vars
+                    .entry(name.clone())
+                    .or_insert((None, Value::unknown()));
+                match var.0 {
+                    // 2回目以降はcopy constraintで最初のcellとつなぐ
+                    Some(first) => chip.copy(layouter, first, cell),
+                    None => {
+                        var.0 = Some(cell);
+                        Ok(())
+                    }
+                }
+            }
+            Operand::Wire(wire) => chip.copy(layouter, wire.cell, cell),
+        }
+    }
+
+    pub(super) fn add(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        a: Operand<F>,
+        b: Operand<F>,
+    ) -> Result<Operand<F>, Error> {
+        match (a, b) {
+            (Operand::Constant(a), Operand::Constant(b)) => Ok(Operand::Constant(a + b)),
+            (Operand::Constant(c), x) | (x, Operand::Constant(c)) => {
+                if c == F::zero() {
+                    return Ok(x);
+                }
+                let v = self.value(&x);
+                let c_assigned = Assigned::from(c);
+                let (lhs, out) = self.chip.raw_add_constant(layouter, c, || {
+                    v.map(|v| (v, v + c_assigned))
+                })?;
+                self.bind(layouter, &x, lhs)?;
+
+                Ok(Operand::Wire(Wire {
+                    cell: out,
+                    value: v.map(|v| v + c_assigned),
+                }))
+            }
+            (a, b) => {
+                let (va, vb) = (self.value(&a), self.value(&b));
+                let (lhs, rhs, out) = self
+                    .chip
+                    .raw_add(layouter, || va.zip(vb).map(|(a, b)| (a, b, a + b)))?;
+                self.bind(layouter, &a, lhs)?;
+                self.bind(layouter, &b, rhs)?;
+
+                Ok(Operand::Wire(Wire {
+                    cell: out,
+                    value: va.zip(vb).map(|(a, b)| a + b),
+                }))
+            }
+        }
+    }
+
+    pub(super) fn mul(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        a: Operand<F>,
+        b: Operand<F>,
+    ) -> Result<Operand<F>, Error> {
+        match (a, b) {
+            (Operand::Constant(a), Operand::Constant(b)) => Ok(Operand::Constant(a * b)),
+            (Operand::Constant(c), x) | (x, Operand::Constant(c)) => {
+                if c == F::one() {
+                    return Ok(x);
+                }
+                let v = self.value(&x);
+                let c_assigned = Assigned::from(c);
+                let (lhs, out) = self.chip.raw_mul_constant(layouter, c, || {
+                    v.map(|v| (v, v * c_assigned))
+                })?;
+                self.bind(layouter, &x, lhs)?;
+
+                Ok(Operand::Wire(Wire {
+                    cell: out,
+                    value: v.map(|v| v * c_assigned),
+                }))
+            }
+            (a, b) => {
+                let (va, vb) = (self.value(&a), self.value(&b));
+                let (lhs, rhs, out) = self
+                    .chip
+                    .raw_multiply(layouter, || va.zip(vb).map(|(a, b)| (a, b, a * b)))?;
+                self.bind(layouter, &a, lhs)?;
+                self.bind(layouter, &b, rhs)?;
+
+                Ok(Operand::Wire(Wire {
+                    cell: out,
+                    value: va.zip(vb).map(|(a, b)| a * b),
+                }))
+            }
+        }
+    }
+
+    // square-and-multiplyなので、x^n はだいたい 2 * log2(n) 個の掛け算になる
+    fn pow(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        base: Operand<F>,
+        mut exp: u32,
+    ) -> Result<Operand<F>, Error> {
+        let mut acc: Option<Operand<F>> = None;
+        let mut square = base;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc = Some(match acc {
+                    Some(acc) => self.mul(layouter, acc, square.clone())?,
+                    None => square.clone(),
+                });
+            }
+            exp >>= 1;
+            if exp > 0 {
+                square = self.mul(layouter, square.clone(), square)?;
+            }
+        }
+
+        Ok(acc.unwrap_or(Operand::Constant(F::one())))
+    }
+
+    /// Makes sure the result lives in a cell, so it can be copied or exposed.
+    pub(super) fn place(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        operand: Operand<F>,
+    ) -> Result<Wire<F>, Error> {
+        match operand {
+            Operand::Wire(wire) => Ok(wire),
+            Operand::Var(ref name) => {
+                let value = self.value(&operand);
+                if let Some((Some(cell), _)) = self.vars.get(name) {
+                    return Ok(Wire { cell: *cell, value });
+                }
+                // l + 0 = o で変数を1つのcellに置く
+                let (lhs, _) = self
+                    .chip
+                    .raw_add_constant(layouter, F::zero(), || value.map(|v| (v, v)))?;
+                self.bind(layouter, &operand, lhs)?;
+
+                Ok(Wire { cell: lhs, value })
+            }
+            Operand::Constant(c) => {
+                // l * 0 = o で0を作ってから、0 + c = o
+                let zero = Assigned::from(F::zero());
+                let (_, zero_cell) = self.chip.raw_mul_constant(layouter, F::zero(), || {
+                    Value::known((zero, zero))
+                })?;
+                let (lhs, out) = self.chip.raw_add_constant(layouter, c, || {
+                    Value::known((zero, Assigned::from(c)))
+                })?;
+                self.chip.copy(layouter, zero_cell, lhs)?;
+
+                Ok(Wire {
+                    cell: out,
+                    value: Value::known(Assigned::from(c)),
+                })
+            }
+        }
+    }
+
+    /// Constrains two operands to be equal.
+    pub(super) fn assert_equal(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        a: Operand<F>,
+        b: Operand<F>,
+    ) -> Result<(), Error> {
+        match (a, b) {
+            // 定数同士はwitnessに関係なく成り立つか成り立たないかのどちらか
+            (Operand::Constant(a), Operand::Constant(b)) => {
+                if a == b {
+                    Ok(())
+                } else {
+                    Err(Error::Synthesis)
+                }
+            }
+            (Operand::Constant(c), x) | (x, Operand::Constant(c)) => {
+                let wire = self.place(layouter, x)?;
+                self.chip.assert_constant(layouter, wire.cell, c)
+            }
+            (a, b) => {
+                let a = self.place(layouter, a)?;
+                let b = self.place(layouter, b)?;
+                self.chip.copy(layouter, a.cell, b.cell)
+            }
+        }
+    }
+}
+
+/// Proves knowledge of witnesses such that each output expression evaluates to
+/// the public input in the same row.
+#[derive(Default)]
+pub struct ExpressionCircuit<F: FieldExt> {
+    outputs: Vec<Expr>,
+    witnesses: BTreeMap<String, Value<F>>,
+}
+
+impl<F: FieldExt> ExpressionCircuit<F> {
+    pub fn new(outputs: Vec<Expr>) -> Self {
+        Self {
+            outputs,
+            witnesses: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_witness(mut self, name: &str, value: Value<F>) -> Self {
+        self.witnesses.insert(name.to_string(), value);
+        self
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for ExpressionCircuit<F> {
+    type Config = TutorialConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.outputs.clone())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        TutorialChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = TutorialChip::new(config);
+        let mut compiler = ExpressionCompiler::new(&chip);
+        for (name, value) in &self.witnesses {
+            compiler.witness(name, *value);
+        }
+
+        for (row, expr) in self.outputs.iter().enumerate() {
+            let wire = compiler.compile(&mut layouter, expr)?;
+            chip.expose_public(&mut layouter, wire.cell, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn e0_expression_parse() {
+    let parsed: Expr = "x^3 + x + 5".parse().unwrap();
+    let built = Expr::var("x").pow(3) + Expr::var("x") + Expr::constant(5);
+    assert_eq!(parsed, built);
+
+    let parsed: Expr = "-(a - 2) * b".parse().unwrap();
+    let built = -(Expr::var("a") - Expr::constant(2)) * Expr::var("b");
+    assert_eq!(parsed, built);
+
+    assert_eq!("x +".parse::<Expr>().unwrap_err().position, 3);
+    assert_eq!("(x * y".parse::<Expr>().unwrap_err().position, 6);
+    assert_eq!("x ^ y".parse::<Expr>().unwrap_err().position, 4);
+    assert_eq!("x y".parse::<Expr>().unwrap_err().position, 2);
+}
+
+#[test]
+fn e0_expression_circuit() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let k = 5;
+    // e2_vitalik_exampleと同じ式を、手で配線せずに書く
+    let vitalik: Expr = "x^3 + x + 5".parse().unwrap();
+    let other: Expr = "(x - y) * (x + y) - 2 * x * y^2".parse().unwrap();
+
+    let vars = BTreeMap::from([("x".to_string(), Fp::from(3)), ("y".to_string(), Fp::from(2))]);
+    let outputs = vec![vitalik.evaluate(&vars).unwrap(), other.evaluate(&vars).unwrap()];
+    assert_eq!(outputs[0], Fp::from(35));
+    assert_eq!(outputs[1], -Fp::from(19));
+
+    let circuit = ExpressionCircuit::new(vec![vitalik, other])
+        .with_witness("x", Value::known(Fp::from(3)))
+        .with_witness("y", Value::known(Fp::from(2)));
+
+    let prover = MockProver::run(k, &circuit, vec![outputs.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    assert_sound(k, &circuit, vec![outputs]);
+}
+
+#[test]
+fn e0_expression_constant_and_variable_outputs() {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let k = 4;
+    let circuit = ExpressionCircuit::new(vec![
+        "2^3 + 1".parse().unwrap(),
+        Expr::var("x"),
+        "x * 1 + 0".parse().unwrap(),
+    ])
+    .with_witness("x", Value::known(Fp::from(11)));
+
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(9), Fp::from(11), Fp::from(11)]])
+        .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(9), Fp::from(12), Fp::from(12)]])
+        .unwrap();
+    assert!(prover.verify().is_err());
+}
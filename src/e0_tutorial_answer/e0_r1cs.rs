@@ -0,0 +1,438 @@
+use std::io::{self, Read};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use super::e0_expression::{ExpressionCompiler, Operand};
+use super::{TutorialChip, TutorialComposer, TutorialConfig};
+
+// circomが出力する .r1cs と .wtns を読んで、TutorialChipの回路にする。
+//
+//   (A·w) * (B·w) = (C·w)
+//
+// 線形結合 A·w, B·w, C·w は raw_mul_constant と raw_add の列になり、
+// A·w * B·w が raw_multiply、最後に C·w とcopy (定数ならassert_constant) でつなぐ。
+// w_0 は常に1なので、w_0 の項は定数としてfixed columnに入る。
+//
+// 体はファイルの素数と F の位数が一致している必要がある。
+// pastaのFpで使うときは `circom --prime pallas` でコンパイルする。
+
+/// `(wire, coefficient)` pairs; the wire `0` is the constant one.
+pub type LinearCombination<F> = Vec<(usize, F)>;
+
+/// A single rank-1 constraint `a * b = c`.
+#[derive(Debug, Clone)]
+pub struct R1csConstraint<F: FieldExt> {
+    pub a: LinearCombination<F>,
+    pub b: LinearCombination<F>,
+    pub c: LinearCombination<F>,
+}
+
+/// The constraint system of a circom `.r1cs` file.
+#[derive(Debug, Clone)]
+pub struct R1cs<F: FieldExt> {
+    /// Number of wires, including the constant wire `0`.
+    pub num_wires: usize,
+    /// Public outputs followed by public inputs, i.e. the wires `1..=num_public`.
+    pub num_public: usize,
+    pub constraints: Vec<R1csConstraint<F>>,
+}
+
+/// Reads a circom `.r1cs` file (version 1).
+///
+/// Fails if the file's prime is not the modulus of `F`.
+pub fn read_r1cs<F: FieldExt>(mut reader: impl Read) -> io::Result<R1cs<F>> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    let sections = read_sections(&data, b"r1cs")?;
+
+    let mut header = Bytes::new(find_section(&sections, 1)?);
+    let n8 = header.u32()? as usize;
+    check_prime::<F>(header.take(n8)?)?;
+    let num_wires = header.u32()? as usize;
+    let num_pub_out = header.u32()? as usize;
+    let num_pub_in = header.u32()? as usize;
+    let _num_prv_in = header.u32()?;
+    let _num_labels = header.u64()?;
+    let num_constraints = header.u32()? as usize;
+
+    let num_public = num_pub_out + num_pub_in;
+    if num_public >= num_wires {
+        return Err(invalid("more public signals than wires"));
+    }
+
+    let mut body = Bytes::new(find_section(&sections, 2)?);
+    let constraints = (0..num_constraints)
+        .map(|_| {
+            Ok(R1csConstraint {
+                a: read_linear_combination(&mut body, n8, num_wires)?,
+                b: read_linear_combination(&mut body, n8, num_wires)?,
+                c: read_linear_combination(&mut body, n8, num_wires)?,
+            })
+        })
+        .collect::<io::Result<_>>()?;
+
+    Ok(R1cs {
+        num_wires,
+        num_public,
+        constraints,
+    })
+}
+
+/// Reads a circom `.wtns` file (version 2) and returns the value of every wire.
+pub fn read_wtns<F: FieldExt>(mut reader: impl Read) -> io::Result<Vec<F>> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    let sections = read_sections(&data, b"wtns")?;
+
+    let mut header = Bytes::new(find_section(&sections, 1)?);
+    let n8 = header.u32()? as usize;
+    check_prime::<F>(header.take(n8)?)?;
+    let num_witness = header.u32()? as usize;
+
+    let mut body = Bytes::new(find_section(&sections, 2)?);
+    (0..num_witness).map(|_| body.field(n8)).collect()
+}
+
+fn read_linear_combination<F: FieldExt>(
+    bytes: &mut Bytes,
+    n8: usize,
+    num_wires: usize,
+) -> io::Result<LinearCombination<F>> {
+    let num_terms = bytes.u32()?;
+    (0..num_terms)
+        .map(|_| {
+            let wire = bytes.u32()? as usize;
+            if wire >= num_wires {
+                return Err(invalid("wire id out of range"));
+            }
+            Ok((wire, bytes.field(n8)?))
+        })
+        .collect()
+}
+
+fn read_sections<'a>(data: &'a [u8], magic: &[u8; 4]) -> io::Result<Vec<(u32, &'a [u8])>> {
+    let mut bytes = Bytes::new(data);
+    if bytes.take(4)? != magic {
+        return Err(invalid("unexpected magic number"));
+    }
+    let _version = bytes.u32()?;
+    let num_sections = bytes.u32()?;
+
+    (0..num_sections)
+        .map(|_| {
+            let section_type = bytes.u32()?;
+            let size = bytes.u64()? as usize;
+            Ok((section_type, bytes.take(size)?))
+        })
+        .collect()
+}
+
+fn find_section<'a>(sections: &[(u32, &'a [u8])], section_type: u32) -> io::Result<&'a [u8]> {
+    sections
+        .iter()
+        .find(|(t, _)| *t == section_type)
+        .map(|(_, data)| *data)
+        .ok_or_else(|| invalid(&format!("missing section {}", section_type)))
+}
+
+// 素数 p は F の中では p ≡ 0 になる。pは素数なので、0になるのは p が F の位数そのときだけ。
+fn check_prime<F: FieldExt>(prime: &[u8]) -> io::Result<()> {
+    if prime.iter().all(|b| *b == 0) || from_le_bytes::<F>(prime) != F::zero() {
+        return Err(invalid("the file was compiled for a different prime field"));
+    }
+    Ok(())
+}
+
+fn from_le_bytes<F: FieldExt>(bytes: &[u8]) -> F {
+    bytes.iter().rev().fold(F::zero(), |acc, b| {
+        acc * F::from(256u64) + F::from(u64::from(*b))
+    })
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct Bytes<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn field<F: FieldExt>(&mut self, n8: usize) -> io::Result<F> {
+        Ok(from_le_bytes(self.take(n8)?))
+    }
+}
+
+/// An imported R1CS as a halo2 circuit on the TutorialChip.
+///
+/// The public signals are exposed in the instance column in circom's order:
+/// outputs first, then public inputs.
+#[derive(Debug, Clone)]
+pub struct R1csCircuit<F: FieldExt> {
+    r1cs: R1cs<F>,
+    witness: Option<Vec<F>>,
+}
+
+impl<F: FieldExt> R1csCircuit<F> {
+    pub fn new(r1cs: R1cs<F>, witness: Option<Vec<F>>) -> Self {
+        Self { r1cs, witness }
+    }
+
+    /// The values the instance column must hold, taken from the witness.
+    pub fn public_inputs(&self) -> Option<Vec<F>> {
+        self.witness
+            .as_ref()
+            .and_then(|witness| witness.get(1..=self.r1cs.num_public))
+            .map(|public| public.to_vec())
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for R1csCircuit<F> {
+    type Config = TutorialConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.r1cs.clone(), None)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        TutorialChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = TutorialChip::new(config);
+        let mut compiler = ExpressionCompiler::new(&chip);
+        if let Some(witness) = &self.witness {
+            if witness.len() != self.r1cs.num_wires {
+                return Err(Error::Synthesis);
+            }
+            for (wire, value) in witness.iter().enumerate().skip(1) {
+                compiler.witness(&wire_name(wire), Value::known(*value));
+            }
+        }
+
+        for constraint in &self.r1cs.constraints {
+            let a = lower_linear_combination(&mut compiler, &mut layouter, &constraint.a)?;
+            let b = lower_linear_combination(&mut compiler, &mut layouter, &constraint.b)?;
+            let c = lower_linear_combination(&mut compiler, &mut layouter, &constraint.c)?;
+
+            let ab = compiler.mul(&mut layouter, a, b)?;
+            compiler.assert_equal(&mut layouter, ab, c)?;
+        }
+
+        for wire in 1..=self.r1cs.num_public {
+            let placed = compiler.place(&mut layouter, Operand::Var(wire_name(wire)))?;
+            chip.expose_public(&mut layouter, placed.cell, wire - 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn wire_name(wire: usize) -> String {
+    format!("w{}", wire)
+}
+
+fn lower_linear_combination<F: FieldExt>(
+    compiler: &mut ExpressionCompiler<F>,
+    layouter: &mut impl Layouter<F>,
+    lc: &LinearCombination<F>,
+) -> Result<Operand<F>, Error> {
+    let mut acc = Operand::Constant(F::zero());
+    for (wire, coeff) in lc {
+        if *coeff == F::zero() {
+            continue;
+        }
+        let term = if *wire == 0 {
+            Operand::Constant(*coeff)
+        } else {
+            compiler.mul(
+                layouter,
+                Operand::Var(wire_name(*wire)),
+                Operand::Constant(*coeff),
+            )?
+        };
+        acc = compiler.add(layouter, acc, term)?;
+    }
+
+    Ok(acc)
+}
+
+// テスト用に .r1cs / .wtns を組み立てる。circomで
+//
+//   template Cubic() {
+//       signal input x;
+//       signal output out;
+//       signal x_sq <== x * x;
+//       signal x_cu <== x_sq * x;
+//       out <== x_cu + x + 5;
+//   }
+//
+// をコンパイルしたのと同じ形 (線形な制約は A = B = 0 になる)。
+#[cfg(test)]
+fn cubic_files<F: FieldExt>(witness: &[u64]) -> (Vec<u8>, Vec<u8>) {
+    fn field<F: FieldExt>(value: F) -> Vec<u8> {
+        value.to_repr().as_ref().to_vec()
+    }
+    fn prime<F: FieldExt>() -> Vec<u8> {
+        // (p - 1) + 1
+        let mut bytes = field(-F::one());
+        for b in bytes.iter_mut() {
+            let (sum, carry) = b.overflowing_add(1);
+            *b = sum;
+            if !carry {
+                break;
+            }
+        }
+        bytes
+    }
+    fn file(magic: &[u8], version: u32, sections: Vec<(u32, Vec<u8>)>) -> Vec<u8> {
+        let mut out = magic.to_vec();
+        out.extend(version.to_le_bytes());
+        out.extend((sections.len() as u32).to_le_bytes());
+        for (section_type, data) in sections {
+            out.extend(section_type.to_le_bytes());
+            out.extend((data.len() as u64).to_le_bytes());
+            out.extend(data);
+        }
+        out
+    }
+    fn lc<F: FieldExt>(terms: &[(u32, F)]) -> Vec<u8> {
+        let mut out = (terms.len() as u32).to_le_bytes().to_vec();
+        for (wire, coeff) in terms {
+            out.extend(wire.to_le_bytes());
+            out.extend(field(*coeff));
+        }
+        out
+    }
+
+    // wires: 0 = one, 1 = out, 2 = x, 3 = x_sq, 4 = x_cu
+    let one = F::one();
+    let constraints: [[Vec<(u32, F)>; 3]; 3] = [
+        [vec![(2, one)], vec![(2, one)], vec![(3, one)]],
+        [vec![(3, one)], vec![(2, one)], vec![(4, one)]],
+        [
+            vec![],
+            vec![],
+            vec![(1, one), (4, -one), (2, -one), (0, -F::from(5u64))],
+        ],
+    ];
+
+    let mut header = 32u32.to_le_bytes().to_vec();
+    header.extend(prime::<F>());
+    for n in [5u32, 1, 0, 1] {
+        header.extend(n.to_le_bytes());
+    }
+    header.extend(5u64.to_le_bytes());
+    header.extend((constraints.len() as u32).to_le_bytes());
+
+    let mut body = vec![];
+    for [a, b, c] in constraints.iter() {
+        body.extend(lc(a));
+        body.extend(lc(b));
+        body.extend(lc(c));
+    }
+    let r1cs = file(b"r1cs", 1, vec![(1, header), (2, body)]);
+
+    let mut header = 32u32.to_le_bytes().to_vec();
+    header.extend(prime::<F>());
+    header.extend((witness.len() as u32).to_le_bytes());
+    let body = witness.iter().flat_map(|w| field(F::from(*w))).collect();
+    let wtns = file(b"wtns", 2, vec![(1, header), (2, body)]);
+
+    (r1cs, wtns)
+}
+
+#[test]
+fn e0_r1cs_import() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let k = 5;
+    let (r1cs, wtns) = cubic_files::<Fp>(&[1, 35, 3, 9, 27]);
+    let r1cs = read_r1cs::<Fp>(&r1cs[..]).unwrap();
+    let witness = read_wtns::<Fp>(&wtns[..]).unwrap();
+    assert_eq!(r1cs.num_wires, 5);
+    assert_eq!(r1cs.num_public, 1);
+    assert_eq!(r1cs.constraints.len(), 3);
+
+    let circuit = R1csCircuit::new(r1cs.clone(), Some(witness));
+    let public_inputs = circuit.public_inputs().unwrap();
+    assert_eq!(public_inputs, vec![Fp::from(35)]);
+
+    let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    assert_sound(k, &circuit, vec![public_inputs]);
+
+    // x_sq が x * x になっていないwitness
+    let (_, wtns) = cubic_files::<Fp>(&[1, 35, 3, 10, 27]);
+    let circuit = R1csCircuit::new(r1cs, Some(read_wtns(&wtns[..]).unwrap()));
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(35)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn e0_r1cs_wrong_prime() {
+    use halo2_proofs::pasta::{Fp, Fq};
+
+    // Fq向けのファイルはFpでは読めない
+    let (r1cs, wtns) = cubic_files::<Fq>(&[1, 35, 3, 9, 27]);
+    assert!(read_r1cs::<Fp>(&r1cs[..]).is_err());
+    assert!(read_wtns::<Fp>(&wtns[..]).is_err());
+    assert!(read_r1cs::<Fq>(&r1cs[..]).is_ok());
+
+    let truncated = &r1cs[..r1cs.len() - 1];
+    assert!(read_r1cs::<Fq>(truncated).is_err());
+}
+
+#[test]
+fn e0_r1cs_prove_verify() {
+    use crate::prover::{prove, setup, verify};
+    use halo2_proofs::pasta::Fp;
+
+    let k = 5;
+    let (r1cs, wtns) = cubic_files::<Fp>(&[1, 35, 3, 9, 27]);
+    let circuit = R1csCircuit::new(
+        read_r1cs(&r1cs[..]).unwrap(),
+        Some(read_wtns(&wtns[..]).unwrap()),
+    );
+    let public_inputs = circuit.public_inputs().unwrap();
+
+    let (params, pk) = setup(k, &circuit).unwrap();
+    let proof = prove(&params, &pk, circuit, &[&public_inputs]).unwrap();
+    assert!(verify(&params, pk.get_vk(), &proof, &[&public_inputs]).is_ok());
+    assert!(verify(&params, pk.get_vk(), &proof, &[&[Fp::from(36)]]).is_err());
+}
@@ -0,0 +1,576 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+// e4でRANGE = 2にすればbitのチェックにはなるけど、ビット分解はできない。
+// ここではvalueをnビットに分けて、上のビットから足していくrunning sumで元に戻す。
+//
+//   acc_0 = b_{n-1}
+//   acc_i = 2 * acc_{i-1} + b_{n-1-i}
+//   acc_{n-1} = value
+//
+//   bit     | acc       | q_bit | q_first | q_running
+//  ---------+-----------+-------+---------+----------
+//   b_{n-1} | acc_0     |   1   |    1    |
+//   b_{n-2} | acc_1     |   1   |         |    1
+//   ..      |           |       |         |
+//   b_0     | value     |   1   |         |    1
+//
+// n < NUM_BITSならaccはpを超えないので、分解は1通りしかない。
+//
+// 論理演算はbitのcellを a | b | out に並べて、
+//
+//   AND: out = a * b
+//   OR:  out = a + b - a * b
+//   XOR: out = a + b - 2 * a * b
+//   NOT: out = 1 - a
+//
+// a, bがbooleanならoutもbooleanなので、outのbooleanチェックはいらない。
+
+/// A cell whose value was checked to be 0 or 1. It can only be created by
+/// [`BitsChip`], so holding one is the guarantee.
+#[derive(Debug, Clone)]
+pub struct Bit<F: FieldExt>(AssignedCell<Assigned<F>, F>);
+
+impl<F: FieldExt> Bit<F> {
+    pub fn cell(&self) -> &AssignedCell<Assigned<F>, F> {
+        &self.0
+    }
+}
+
+/// A value together with its `num_bits` boolean cells.
+#[derive(Debug, Clone)]
+pub struct Bits<F: FieldExt> {
+    value: AssignedCell<Assigned<F>, F>,
+    le: Vec<Bit<F>>,
+}
+
+impl<F: FieldExt> Bits<F> {
+    /// The cell holding the recomposed value.
+    pub fn cell(&self) -> &AssignedCell<Assigned<F>, F> {
+        &self.value
+    }
+
+    /// The bits, least significant first.
+    pub fn le(&self) -> &[Bit<F>] {
+        &self.le
+    }
+
+    /// The bits, most significant first.
+    pub fn be(&self) -> Vec<Bit<F>> {
+        self.le.iter().rev().cloned().collect()
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.le.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BitsConfig {
+    // 分解ではbit, acc、論理演算ではa, b, outとして使う
+    advice: [Column<Advice>; 3],
+    q_bit: Selector,
+    q_first: Selector,
+    q_running: Selector,
+    q_and: Selector,
+    q_or: Selector,
+    q_xor: Selector,
+    q_not: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct BitsChip<F: FieldExt> {
+    config: BitsConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> BitsChip<F> {
+    pub fn construct(config: BitsConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> BitsConfig {
+        let [q_bit, q_first, q_running, q_and, q_or, q_xor, q_not] =
+            [(); 7].map(|_| meta.selector());
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        let one = || Expression::Constant(F::one());
+
+        meta.create_gate("running sum", |meta| {
+            let q_bit = meta.query_selector(q_bit);
+            let q_first = meta.query_selector(q_first);
+            let q_running = meta.query_selector(q_running);
+            let bit = meta.query_advice(advice[0], Rotation::cur());
+            let acc = meta.query_advice(advice[1], Rotation::cur());
+            let acc_prev = meta.query_advice(advice[1], Rotation::prev());
+
+            vec![
+                q_bit * bit.clone() * (one() - bit.clone()),
+                q_first * (acc.clone() - bit.clone()),
+                q_running * (acc - acc_prev * Expression::Constant(F::from(2u64)) - bit),
+            ]
+        });
+
+        meta.create_gate("logic", |meta| {
+            let q_and = meta.query_selector(q_and);
+            let q_or = meta.query_selector(q_or);
+            let q_xor = meta.query_selector(q_xor);
+            let q_not = meta.query_selector(q_not);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[2], Rotation::cur());
+            let ab = a.clone() * b.clone();
+
+            vec![
+                q_and * (out.clone() - ab.clone()),
+                q_or * (out.clone() - (a.clone() + b.clone() - ab.clone())),
+                q_xor * (out.clone() - (a.clone() + b - ab * Expression::Constant(F::from(2u64)))),
+                q_not * (out - (one() - a)),
+            ]
+        });
+
+        BitsConfig {
+            advice,
+            q_bit,
+            q_first,
+            q_running,
+            q_and,
+            q_or,
+            q_xor,
+            q_not,
+        }
+    }
+
+    /// Witnesses `value` and decomposes it into `num_bits` bits. Fails to
+    /// verify if `value` is not below `2^num_bits`.
+    ///
+    /// Fails with `Error::Synthesis` unless `0 < num_bits < F::NUM_BITS`.
+    pub fn decompose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<Assigned<F>>,
+        num_bits: usize,
+    ) -> Result<Bits<F>, Error> {
+        let bits = value.map(|v| bits_be(v.evaluate(), num_bits));
+        layouter.assign_region(
+            || "decompose",
+            |mut region| {
+                self.running_sum(
+                    &mut region,
+                    num_bits,
+                    |region, row| {
+                        let bit = bits
+                            .as_ref()
+                            .map(|bits| Assigned::from(F::from(bits[row] as u64)));
+                        region.assign_advice(|| "bit", self.config.advice[0], row, || bit)
+                    },
+                    |region, row| {
+                        region.assign_advice(|| "value", self.config.advice[1], row, || value)
+                    },
+                )
+            },
+        )
+    }
+
+    /// Decomposes the value of an existing cell into `num_bits` bits.
+    ///
+    /// Fails with `Error::Synthesis` unless `0 < num_bits < F::NUM_BITS`.
+    pub fn copy_decompose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<Assigned<F>, F>,
+        num_bits: usize,
+    ) -> Result<Bits<F>, Error> {
+        let bits = value.value().map(|v| bits_be(v.evaluate(), num_bits));
+        layouter.assign_region(
+            || "decompose",
+            |mut region| {
+                self.running_sum(
+                    &mut region,
+                    num_bits,
+                    |region, row| {
+                        let bit = bits
+                            .as_ref()
+                            .map(|bits| Assigned::from(F::from(bits[row] as u64)));
+                        region.assign_advice(|| "bit", self.config.advice[0], row, || bit)
+                    },
+                    |region, row| value.copy_advice(|| "value", region, self.config.advice[1], row),
+                )
+            },
+        )
+    }
+
+    /// `sum bits[i] * 2^i`
+    pub fn recompose_le(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[Bit<F>],
+    ) -> Result<Bits<F>, Error> {
+        let be = bits.iter().rev().cloned().collect::<Vec<_>>();
+        self.recompose_be(layouter, &be)
+    }
+
+    /// `sum bits[i] * 2^(n - 1 - i)`
+    ///
+    /// Fails with `Error::Synthesis` unless `0 < bits.len() < F::NUM_BITS`.
+    pub fn recompose_be(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: &[Bit<F>],
+    ) -> Result<Bits<F>, Error> {
+        let value = bits
+            .iter()
+            .fold(Value::known(Assigned::from(F::zero())), |acc, bit| {
+                acc.zip(bit.cell().value())
+                    .map(|(acc, bit)| acc + acc + *bit)
+            });
+        layouter.assign_region(
+            || "recompose",
+            |mut region| {
+                self.running_sum(
+                    &mut region,
+                    bits.len(),
+                    |region, row| {
+                        bits[row]
+                            .cell()
+                            .copy_advice(|| "bit", region, self.config.advice[0], row)
+                    },
+                    |region, row| {
+                        region.assign_advice(|| "value", self.config.advice[1], row, || value)
+                    },
+                )
+            },
+        )
+    }
+
+    /// Witnesses a single bit.
+    pub fn witness_bit(
+        &self,
+        layouter: impl Layouter<F>,
+        bit: Value<bool>,
+    ) -> Result<Bit<F>, Error> {
+        let value = bit.map(|bit| Assigned::from(F::from(bit as u64)));
+        let bits = self.decompose(layouter, value, 1)?;
+        Ok(bits.le[0].clone())
+    }
+
+    pub fn and(&self, layouter: impl Layouter<F>, a: &Bit<F>, b: &Bit<F>) -> Result<Bit<F>, Error> {
+        self.logic(layouter, "and", self.config.q_and, a, Some(b), |a, b| a * b)
+    }
+
+    pub fn or(&self, layouter: impl Layouter<F>, a: &Bit<F>, b: &Bit<F>) -> Result<Bit<F>, Error> {
+        self.logic(layouter, "or", self.config.q_or, a, Some(b), |a, b| {
+            a + b - a * b
+        })
+    }
+
+    pub fn xor(&self, layouter: impl Layouter<F>, a: &Bit<F>, b: &Bit<F>) -> Result<Bit<F>, Error> {
+        self.logic(layouter, "xor", self.config.q_xor, a, Some(b), |a, b| {
+            a + b - (a * b).double()
+        })
+    }
+
+    pub fn not(&self, layouter: impl Layouter<F>, a: &Bit<F>) -> Result<Bit<F>, Error> {
+        self.logic(layouter, "not", self.config.q_not, a, None, |a, _| {
+            F::one() - a
+        })
+    }
+
+    // a, bの値は0か1なので、opはFの上で計算してしまっていい
+    fn logic(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        selector: Selector,
+        a: &Bit<F>,
+        b: Option<&Bit<F>>,
+        op: impl Fn(F, F) -> F,
+    ) -> Result<Bit<F>, Error> {
+        let [a_col, b_col, out_col] = self.config.advice;
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                let a = a.cell().copy_advice(|| "a", &mut region, a_col, 0)?;
+                // NOTのときはbを使わないけど、何か置いておく
+                let b = match b {
+                    Some(b) => b.cell().copy_advice(|| "b", &mut region, b_col, 0)?,
+                    None => region.assign_advice(
+                        || "b",
+                        b_col,
+                        0,
+                        || Value::known(Assigned::from(F::zero())),
+                    )?,
+                };
+                let out = a
+                    .value()
+                    .zip(b.value())
+                    .map(|(a, b)| Assigned::from(op(a.evaluate(), b.evaluate())));
+                region.assign_advice(|| "out", out_col, 0, || out).map(Bit)
+            },
+        )
+    }
+
+    fn running_sum<B, V>(
+        &self,
+        region: &mut Region<'_, F>,
+        num_bits: usize,
+        mut assign_bit: B,
+        assign_value: V,
+    ) -> Result<Bits<F>, Error>
+    where
+        B: FnMut(&mut Region<'_, F>, usize) -> Result<AssignedCell<Assigned<F>, F>, Error>,
+        V: FnOnce(&mut Region<'_, F>, usize) -> Result<AssignedCell<Assigned<F>, F>, Error>,
+    {
+        // 0ビットは分解するものがなく、体のサイズ以上だとaccがpを超えて回り込む
+        if num_bits == 0 || num_bits >= F::NUM_BITS as usize {
+            return Err(Error::Synthesis);
+        }
+        let config = &self.config;
+        let last = num_bits - 1;
+
+        let mut be = Vec::with_capacity(num_bits);
+        for row in 0..num_bits {
+            config.q_bit.enable(region, row)?;
+            if row == 0 {
+                config.q_first.enable(region, row)?;
+            } else {
+                config.q_running.enable(region, row)?;
+            }
+            be.push(Bit(assign_bit(region, row)?));
+        }
+
+        // 最後のaccはvalueそのもの。bitから計算し直さないので、
+        // valueが2^num_bitsに収まっていなければここで落ちる
+        let mut acc = Value::known(Assigned::from(F::zero()));
+        for (row, bit) in be.iter().enumerate().take(last) {
+            acc = acc
+                .zip(bit.cell().value())
+                .map(|(acc, bit)| acc + acc + *bit);
+            region.assign_advice(|| "acc", config.advice[1], row, || acc)?;
+        }
+        let value = assign_value(region, last)?;
+
+        be.reverse();
+        Ok(Bits { value, le: be })
+    }
+}
+
+/// The lowest `num_bits` bits of `value`, most significant first.
+fn bits_be<F: FieldExt>(value: F, num_bits: usize) -> Vec<bool> {
+    let repr = value.to_repr();
+    (0..num_bits)
+        .rev()
+        .map(|i| {
+            repr.as_ref()
+                .get(i / 8)
+                .map_or(false, |byte| (byte >> (i % 8)) & 1 == 1)
+        })
+        .collect()
+}
+
+/// Exposes `[x ^ y, x & y, x | y, !x, x with its bits reversed]` for 8-bit `x`, `y`.
+#[derive(Default)]
+pub struct MyCircuit<F> {
+    x: Value<F>,
+    y: Value<F>,
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    pub const NUM_BITS: usize = 8;
+
+    pub fn new(x: Value<F>, y: Value<F>) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = (BitsConfig, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        (BitsChip::configure(meta, advice), instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, instance): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = BitsChip::construct(config);
+        let n = Self::NUM_BITS;
+
+        let x = chip.decompose(layouter.namespace(|| "x"), self.x.map(Assigned::from), n)?;
+        let y = chip.decompose(layouter.namespace(|| "y"), self.y.map(Assigned::from), n)?;
+
+        let mut xor = vec![];
+        let mut and = vec![];
+        let mut or = vec![];
+        let mut not = vec![];
+        for (a, b) in x.le().iter().zip(y.le()) {
+            xor.push(chip.xor(layouter.namespace(|| "xor"), a, b)?);
+            and.push(chip.and(layouter.namespace(|| "and"), a, b)?);
+            or.push(chip.or(layouter.namespace(|| "or"), a, b)?);
+            not.push(chip.not(layouter.namespace(|| "not"), a)?);
+        }
+
+        let outputs = [
+            chip.recompose_le(layouter.namespace(|| "x ^ y"), &xor)?,
+            chip.recompose_le(layouter.namespace(|| "x & y"), &and)?,
+            chip.recompose_le(layouter.namespace(|| "x | y"), &or)?,
+            chip.recompose_le(layouter.namespace(|| "!x"), &not)?,
+            // LEのビットをBEとして読むと逆順になる
+            chip.recompose_be(layouter.namespace(|| "reverse x"), x.le())?,
+        ];
+        for (row, output) in outputs.iter().enumerate() {
+            layouter.constrain_instance(output.cell().cell(), instance, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn e10_bits() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let k = 7;
+    let expected = |x: u8, y: u8| {
+        [x ^ y, x & y, x | y, !x, x.reverse_bits()]
+            .map(|v| Fp::from(v as u64))
+            .to_vec()
+    };
+    let circuit =
+        |x: u64, y: u64| MyCircuit::new(Value::known(Fp::from(x)), Value::known(Fp::from(y)));
+
+    for (x, y) in [
+        (0, 0),
+        (0b1100_1010, 0b1010_0110),
+        (255, 0),
+        (1, 128),
+        (255, 255),
+    ] {
+        let prover = MockProver::run(k, &circuit(x, y), vec![expected(x as u8, y as u8)]).unwrap();
+        assert_eq!(prover.verify(), Ok(()), "x = {}, y = {}", x, y);
+
+        // 左右を入れ替えた答えでは通らない
+        if x != y {
+            let prover =
+                MockProver::run(k, &circuit(x, y), vec![expected(y as u8, x as u8)]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    // 8ビットに入らない
+    let prover = MockProver::run(k, &circuit(256, 0), vec![expected(0, 0)]).unwrap();
+    assert!(prover.verify().is_err());
+
+    assert_sound(
+        k,
+        &circuit(0b0110_1001, 0b1111_0000),
+        vec![expected(0b0110_1001, 0b1111_0000)],
+    );
+}
+
+// field全体に近い幅でも分解できること
+#[test]
+fn e10_bits_wide() {
+    use crate::soundness::mock_verify;
+    use halo2_proofs::{arithmetic::Field, dev::MockProver, pasta::Fp};
+
+    struct Wide {
+        value: Value<Fp>,
+        num_bits: usize,
+    }
+
+    impl Circuit<Fp> for Wide {
+        type Config = BitsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: Value::unknown(),
+                num_bits: self.num_bits,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            BitsChip::configure(meta, advice)
+        }
+
+        fn synthesize(
+            &self,
+            config: BitsConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = BitsChip::construct(config);
+            let bits = chip.decompose(
+                layouter.namespace(|| "value"),
+                self.value.map(Assigned::from),
+                self.num_bits,
+            )?;
+            assert_eq!(bits.num_bits(), self.num_bits);
+
+            // LEで戻しても、BEで戻しても同じ値
+            let le = chip.recompose_le(layouter.namespace(|| "le"), bits.le())?;
+            let be = chip.recompose_be(layouter.namespace(|| "be"), &bits.be())?;
+            layouter.assign_region(
+                || "le == be == value",
+                |mut region| {
+                    region.constrain_equal(le.cell().cell(), bits.cell().cell())?;
+                    region.constrain_equal(be.cell().cell(), bits.cell().cell())
+                },
+            )
+        }
+    }
+
+    let k = 11;
+    let check = |value: Fp, num_bits: usize| {
+        let circuit = Wide {
+            value: Value::known(value),
+            num_bits,
+        };
+        mock_verify(k, &circuit, vec![])
+    };
+
+    let two_pow_200 = Fp::from(2).pow_vartime([200]);
+    assert_eq!(check(two_pow_200, 201), Ok(()));
+    assert_eq!(check(two_pow_200 - Fp::one(), 200), Ok(()));
+    assert!(check(two_pow_200, 200).is_err());
+    assert_eq!(check(Fp::one(), 1), Ok(()));
+    assert!(check(Fp::from(2), 1).is_err());
+
+    // 0ビットと、体のサイズ (Fpは255ビット) 以上の幅は回路が作れない
+    for num_bits in [0, 255] {
+        let circuit = Wide {
+            value: Value::known(Fp::zero()),
+            num_bits,
+        };
+        assert!(
+            matches!(MockProver::run(k, &circuit, vec![]), Err(Error::Synthesis)),
+            "num_bits = {}",
+            num_bits
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::marker::PhantomData;
+
+use halo2_base::halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+
+// e5のテーブルはlookup_table_columnなので、中身はkeygenのときに決まってしまう。
+// ここではテーブル側もadvice columnにして、proofごとに違う中身 (例えば秘密のkey-value map) を引く。
+// zcashのhalo2にはTableColumn相手のlookupしかないので、e6と同じくhalo2-base経由の
+// PSE版halo2のlookup_anyを使う。
+//
+//   key | value | q_table || query_key | query_value | q_lookup
+//  -----+-------+---------++-----------+-------------+----------
+//   k_0 |  v_0  |    1    ||    k_1    |     v_1     |    1
+//   k_1 |  v_1  |    1    ||           |             |
+//
+//   (q_lookup, q_lookup * query_key, q_lookup * query_value)
+//     が (q_table, q_table * key, q_table * value) のどれかの行にある
+//
+// q_tableが0の行は (0, 0, 0) になる。selectorも並べておかないと、
+// 表にない (0, 0) を q_tableが0の行で引けてしまう。
+// 逆にq_lookupが0の行は (0, 0, 0) で、q_tableが0の行がいつもあるので落ちない。
+//
+// テーブルの中身はwitnessなので、何の表なのかを縛るのは使う側の仕事
+// (instanceに出す、hashを取るなど)。同じkeyが2回出てくるのも止めていない。
+
+#[derive(Debug, Clone)]
+pub struct DynamicLookupConfig<F: FieldExt> {
+    key: Column<Advice>,
+    value: Column<Advice>,
+    query_key: Column<Advice>,
+    query_value: Column<Advice>,
+    q_table: Selector,
+    q_lookup: Selector,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> DynamicLookupConfig<F> {
+    /// `advice` is `[key, value, query_key, query_value]`.
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 4]) -> Self {
+        let [key, value, query_key, query_value] = advice;
+        let q_table = meta.complex_selector();
+        let q_lookup = meta.complex_selector();
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.lookup_any("dynamic lookup", |meta| {
+            let q_table = meta.query_selector(q_table);
+            let q_lookup = meta.query_selector(q_lookup);
+            let key = meta.query_advice(key, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+            let query_key = meta.query_advice(query_key, Rotation::cur());
+            let query_value = meta.query_advice(query_value, Rotation::cur());
+
+            vec![
+                (q_lookup.clone(), q_table.clone()),
+                (q_lookup.clone() * query_key, q_table.clone() * key),
+                (q_lookup * query_value, q_table * value),
+            ]
+        });
+
+        Self {
+            key,
+            value,
+            query_key,
+            query_value,
+            q_table,
+            q_lookup,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Witnesses the `(key, value)` entries of the table.
+    ///
+    /// The number of entries is part of the circuit; the contents are not.
+    pub fn load_table(
+        &self,
+        mut layouter: impl Layouter<F>,
+        entries: &[(Value<F>, Value<F>)],
+    ) -> Result<Vec<(AssignedCell<F, F>, AssignedCell<F, F>)>, Error> {
+        layouter.assign_region(
+            || "dynamic table",
+            |mut region| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, (key, value))| {
+                        self.q_table.enable(&mut region, offset)?;
+                        let key = region.assign_advice(|| "key", self.key, offset, || *key)?;
+                        let value =
+                            region.assign_advice(|| "value", self.value, offset, || *value)?;
+                        Ok((key, value))
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Witnesses `key` and `value` and checks that `(key, value)` is an entry
+    /// of the table.
+    pub fn lookup(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: Value<F>,
+        value: Value<F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        layouter.assign_region(
+            || "dynamic lookup",
+            |mut region| {
+                let offset = 0;
+
+                self.q_lookup.enable(&mut region, offset)?;
+                let key = region.assign_advice(|| "key", self.query_key, offset, || key)?;
+                let value = region.assign_advice(|| "value", self.query_value, offset, || value)?;
+                Ok((key, value))
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamicLookupCircuitConfig<F: FieldExt> {
+    lookup: DynamicLookupConfig<F>,
+    instance: Column<Instance>,
+}
+
+/// Looks up each key in a private table and exposes `[key_0, value_0, key_1, value_1, ...]`.
+pub struct DynamicLookupCircuit<F: FieldExt> {
+    table: Vec<(Value<F>, Value<F>)>,
+    queries: Vec<(Value<F>, Value<F>)>,
+}
+
+impl<F: FieldExt> DynamicLookupCircuit<F> {
+    /// `queries` are the `(key, value)` pairs to find in `table`.
+    pub fn new(table: Vec<(Value<F>, Value<F>)>, queries: Vec<(Value<F>, Value<F>)>) -> Self {
+        Self { table, queries }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for DynamicLookupCircuit<F> {
+    type Config = DynamicLookupCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        let unknown = |len| vec![(Value::unknown(), Value::unknown()); len];
+        Self::new(unknown(self.table.len()), unknown(self.queries.len()))
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        DynamicLookupCircuitConfig {
+            lookup: DynamicLookupConfig::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config
+            .lookup
+            .load_table(layouter.namespace(|| "table"), &self.table)?;
+
+        for (i, (key, value)) in self.queries.iter().enumerate() {
+            let (key, value) =
+                config
+                    .lookup
+                    .lookup(layouter.namespace(|| "query"), *key, *value)?;
+            layouter.constrain_instance(key.cell(), config.instance, 2 * i)?;
+            layouter.constrain_instance(value.cell(), config.instance, 2 * i + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn e11_dynamic_lookup() {
+    use halo2_base::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+
+    let k = 5;
+    let known = |pairs: &[(u64, u64)]| {
+        pairs
+            .iter()
+            .map(|(key, value)| (Value::known(Fr::from(*key)), Value::known(Fr::from(*value))))
+            .collect::<Vec<_>>()
+    };
+    let check = |table: &[(u64, u64)], queries: &[(u64, u64)]| {
+        let circuit = DynamicLookupCircuit::new(known(table), known(queries));
+        let instance = queries
+            .iter()
+            .flat_map(|(key, value)| [Fr::from(*key), Fr::from(*value)])
+            .collect();
+        MockProver::run(k, &circuit, vec![instance])
+            .unwrap()
+            .verify()
+    };
+
+    // 同じ形の回路で、表の中身だけが違う
+    let balances = [(1001, 50), (1002, 0), (1003, 7)];
+    let prices = [(1, 300), (2, 120), (3, 1001)];
+    assert_eq!(check(&balances, &[(1003, 7), (1001, 50)]), Ok(()));
+    assert_eq!(check(&prices, &[(3, 1001), (1, 300)]), Ok(()));
+
+    // keyはあるけどvalueが違う、keyがない
+    assert!(check(&balances, &[(1003, 8), (1001, 50)]).is_err());
+    assert!(check(&balances, &[(1004, 7), (1001, 50)]).is_err());
+    // 別の行のkeyとvalueを組み合わせる
+    assert!(check(&balances, &[(1001, 7), (1001, 50)]).is_err());
+
+    // 表にない (0, 0) は、q_tableが0の行では引けない
+    assert!(check(&balances, &[(0, 0), (1001, 50)]).is_err());
+    assert_eq!(check(&[(0, 0), (5, 5), (6, 6)], &[(0, 0), (6, 6)]), Ok(()));
+}
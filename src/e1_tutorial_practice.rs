@@ -318,6 +318,9 @@ impl<F: FieldExt> Circuit<F> for TutorialCircuit<F> {
         // TutorialChipがここを通じて組み込まれる
         let cs = TutorialChip::new(config);
 
+        // ここは練習用なので、raw_multiply/raw_addとcopyを手で並べる形のまま残している。
+        // witnessを計算し直さなくていいTutorialCellを使う版は、e0_tutorial_answerのassign_packedを参照。
+
         // Initialise these values so that we can access them more easily outside the block we actually give them a value in
         // これらの値を初期化して、実際に値を与えるブロックの外でより簡単にアクセスできるようにします。
         let x: Value<Assigned<_>> = self.x.into();
@@ -392,9 +395,8 @@ fn e1_tutorial_practice_test() {
     let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
     assert_eq!(prover.verify(), Ok(()));
 
-    // TODO: This broke when Value was introduced to replace Option. Fix it
     // If we try some other public input, the proof will fail!
     public_inputs[0] += Fp::one();
-    // let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
-    // assert!(prover.verify().is_err());
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
 }
\ No newline at end of file
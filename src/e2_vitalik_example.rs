@@ -1,197 +1,26 @@
 use halo2_proofs::circuit::Value;
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{Cell, Chip, Layouter, SimpleFloorPlanner},
-    plonk::{Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Fixed, Instance},
-    poly::Rotation,
+    circuit::{Chip, Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
 };
-use std::marker::PhantomData;
 
-#[allow(non_snake_case, dead_code)]
-#[derive(Debug, Clone)]
-struct TutorialConfig {
-    l: Column<Advice>,
-    r: Column<Advice>,
-    o: Column<Advice>,
-
-    sl: Column<Fixed>,
-    sr: Column<Fixed>,
-    so: Column<Fixed>,
-    sm: Column<Fixed>,
-    sc: Column<Fixed>,
-    PI: Column<Instance>,
-}
-
-struct TutorialChip<F: FieldExt> {
-    config: TutorialConfig,
-    marker: PhantomData<F>,
-}
-
-impl<F: FieldExt> TutorialChip<F> {
-    fn new(config: TutorialConfig) -> Self {
-        TutorialChip {
-            config,
-            marker: PhantomData,
-        }
-    }
-}
-
-impl<F: FieldExt> Chip<F> for TutorialChip<F> {
-    type Config = TutorialConfig;
-    type Loaded = ();
-
-    fn config(&self) -> &Self::Config {
-        &self.config
-    }
-
-    fn loaded(&self) -> &Self::Loaded {
-        &()
-    }
-}
-
-trait TutorialComposer<F: FieldExt> {
-    fn raw_multiply<FM>(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        f: FM,
-    ) -> Result<(Cell, Cell, Cell), Error>
-    where
-        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>;
-
-    fn raw_add<FM>(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        f: FM,
-    ) -> Result<(Cell, Cell, Cell), Error>
-    where
-        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>;
-
-    /// Ensure two wire values are the same, in effect connecting the wires to each other
-    fn copy(&self, layouter: &mut impl Layouter<F>, a: Cell, b: Cell) -> Result<(), Error>;
-
-    /// Exposes a number as a public input to the circuit.
-    fn expose_public(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        cell: Cell,
-        row: usize,
-    ) -> Result<(), Error>;
-}
-
-impl<F: FieldExt> TutorialComposer<F> for TutorialChip<F> {
-    fn raw_multiply<FM>(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        mut f: FM,
-    ) -> Result<(Cell, Cell, Cell), Error>
-    where
-        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
-    {
-        layouter.assign_region(
-            || "mul",
-            |mut region| {
-                let mut values = None;
-                let lhs = region.assign_advice(
-                    || "lhs",
-                    self.config.l,
-                    0,
-                    || {
-                        values = Some(f());
-                        values.unwrap().map(|v| v.0)
-                    },
-                )?;
-                let rhs = region.assign_advice(
-                    || "rhs",
-                    self.config.r,
-                    0,
-                    || values.unwrap().map(|v| v.1),
-                )?;
-
-                let out = region.assign_advice(
-                    || "out",
-                    self.config.o,
-                    0,
-                    || values.unwrap().map(|v| v.2),
-                )?;
-
-                region.assign_fixed(|| "m", self.config.sm, 0, || Value::known(F::one()))?;
-                region.assign_fixed(|| "o", self.config.so, 0, || Value::known(F::one()))?;
-
-                Ok((lhs.cell(), rhs.cell(), out.cell()))
-            },
-        )
-    }
-
-    fn raw_add<FM>(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        mut f: FM,
-    ) -> Result<(Cell, Cell, Cell), Error>
-    where
-        FM: FnMut() -> Value<(Assigned<F>, Assigned<F>, Assigned<F>)>,
-    {
-        layouter.assign_region(
-            || "mul",
-            |mut region| {
-                let mut values = None;
-                let lhs = region.assign_advice(
-                    || "lhs",
-                    self.config.l,
-                    0,
-                    || {
-                        values = Some(f());
-                        values.unwrap().map(|v| v.0)
-                    },
-                )?;
-                let rhs = region.assign_advice(
-                    || "rhs",
-                    self.config.r,
-                    0,
-                    || values.unwrap().map(|v| v.1),
-                )?;
-
-                let out = region.assign_advice(
-                    || "out",
-                    self.config.o,
-                    0,
-                    || values.unwrap().map(|v| v.2),
-                )?;
-
-                region.assign_fixed(|| "l", self.config.sl, 0, || Value::known(F::one()))?;
-                region.assign_fixed(|| "r", self.config.sr, 0, || Value::known(F::one()))?;
-                region.assign_fixed(|| "o", self.config.so, 0, || Value::known(F::one()))?;
-
-                Ok((lhs.cell(), rhs.cell(), out.cell()))
-            },
-        )
-    }
-
-    fn copy(&self, layouter: &mut impl Layouter<F>, left: Cell, right: Cell) -> Result<(), Error> {
-        layouter.assign_region(
-            || "copy",
-            |mut region| {
-                region.constrain_equal(left, right)?;
-                region.constrain_equal(left, right)
-            },
-        )
-    }
-
-    fn expose_public(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        cell: Cell,
-        row: usize,
-    ) -> Result<(), Error> {
-        layouter.constrain_instance(cell, self.config.PI, row)
-    }
-}
+use crate::e0_tutorial_answer::{TutorialChip, TutorialComposer, TutorialConfig};
 
 #[derive(Default)]
-struct TutorialCircuit<F: FieldExt> {
+pub struct TutorialCircuit<F: FieldExt> {
     x: Value<F>,
     constant: F,
 }
 
+impl<F: FieldExt> TutorialCircuit<F> {
+    /// Proves knowledge of `x` such that `x^3 + x + constant` equals the second
+    /// public input. The public inputs are `[constant, x^3 + x + constant]`.
+    pub fn new(x: Value<F>, constant: F) -> Self {
+        Self { x, constant }
+    }
+}
+
 impl<F: FieldExt> Circuit<F> for TutorialCircuit<F> {
     type Config = TutorialConfig;
     type FloorPlanner = SimpleFloorPlanner;
@@ -201,49 +30,7 @@ impl<F: FieldExt> Circuit<F> for TutorialCircuit<F> {
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let l = meta.advice_column();
-        let r = meta.advice_column();
-        let o = meta.advice_column();
-
-        meta.enable_equality(l);
-        meta.enable_equality(r);
-        meta.enable_equality(o);
-
-        let sm = meta.fixed_column();
-        let sl = meta.fixed_column();
-        let sr = meta.fixed_column();
-        let so = meta.fixed_column();
-        let sc = meta.fixed_column();
-        
-        #[allow(non_snake_case)]
-        let PI = meta.instance_column();
-        meta.enable_equality(PI);
-
-        meta.create_gate("mini plonk", |meta| {
-            let l = meta.query_advice(l, Rotation::cur());
-            let r = meta.query_advice(r, Rotation::cur());
-            let o = meta.query_advice(o, Rotation::cur());
-
-            let sl = meta.query_fixed(sl, Rotation::cur());
-            let sr = meta.query_fixed(sr, Rotation::cur());
-            let so = meta.query_fixed(so, Rotation::cur());
-            let sm = meta.query_fixed(sm, Rotation::cur());
-            let sc = meta.query_fixed(sc, Rotation::cur());
-
-            vec![l.clone() * sl + r.clone() * sr + l * r * sm + (o * so * (-F::one())) + sc]
-        });
-
-        TutorialConfig {
-            l,
-            r,
-            o,
-            sl,
-            sr,
-            so,
-            sm,
-            sc,
-            PI,
-        }
+        TutorialChip::configure(meta)
     }
 
     fn synthesize(
@@ -253,37 +40,28 @@ impl<F: FieldExt> Circuit<F> for TutorialCircuit<F> {
     ) -> Result<(), Error> {
         let cs = TutorialChip::new(config);
 
-        // Initialise these values so that we can access them more easily outside the block we actually give them a value in
-        let x: Value<Assigned<_>> = self.x.into();
-        let consty = Assigned::from(self.constant);
-
-        // Create x^2
-        let (a0, b0, c0) = cs.raw_multiply(&mut layouter, || x.map(|x| (x, x, x * x)))?;
-        cs.copy(&mut layouter, a0, b0)?;
+        let (consty, out) = cs.assign_packed(&mut layouter, "x^3 + x + c", |b| {
+            let x = b.load_private(self.x)?;
+            let consty = b.load_private(Value::known(self.constant))?;
 
-        // Create x^2 * x
-        let (a1, b1, c1) = cs.raw_multiply(&mut layouter, || x.map(|x| (x * x, x, x * x * x)))?;
-        // cs.copy(&mut layouter, a0, b0, b1)?;
-        cs.copy(&mut layouter, a0, b1)?;
-        cs.copy(&mut layouter, c0, a1)?;
+            // Create x^2 and x^2 * x
+            let x2 = b.mul(&x, &x)?;
+            let x3 = b.mul(&x2, &x)?;
 
-        // Create x + x^3 
-        let (a2, b2, c2) = cs.raw_add(&mut layouter, ||
-            x.map(|x| (x, x * x * x, x + x * x * x)))?;
-        cs.copy(&mut layouter, a0, a2)?;
-        cs.copy(&mut layouter, c1, b2)?;
+            // Create x + x^3
+            let x3_x = b.add(&x, &x3)?;
 
-        // Add the constant
-        let (a3, b3, c3) = cs.raw_add(&mut layouter, ||
-            x.map(|x| (x + x * x * x, consty, x + x * x * x + consty)))?;
-        cs.copy(&mut layouter, c2, a3)?;
+            // Add the constant
+            let out = b.add(&x3_x, &consty)?;
+            Ok((consty, out))
+        })?;
 
         // Ensure that the constant in the TutorialCircuit struct is correctly used and that the
         // result of the circuit computation is what is expected. (use expose_public))
-        cs.expose_public(&mut layouter, b3, 0)?;
+        cs.expose_public(&mut layouter, consty.cell(), 0)?;
         // Below is another way to expose a public value, this time the output value of the computation
         // (Use constrain_instance)
-        layouter.constrain_instance(c3, cs.config.PI, 1)?;
+        layouter.constrain_instance(out.cell(), cs.config().PI, 1)?;
 
         Ok(())
     }
@@ -315,11 +93,10 @@ fn e2_vitalik_example_test() {
     let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
     assert_eq!(prover.verify(), Ok(()));
 
-    // TODO: This broke when Value was introduced to replace Option. Fix it
     // If we try some other public input, the proof will fail!
     public_inputs[0] += Fp::one();
-    // let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
-    // assert!(prover.verify().is_err());
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
 }
 
 // cargo test --features "dev-graph" e2_vitalik_example_plot
@@ -342,4 +119,15 @@ fn e2_vitalik_example_plot() {
     halo2_proofs::dev::CircuitLayout::default()
         .render(4, &circuit, &root)
         .unwrap();
-}
\ No newline at end of file
+}
+
+#[test]
+fn e2_vitalik_example_soundness_test() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::pasta::Fp;
+
+    let constant = Fp::from(5);
+    let circuit = TutorialCircuit::new(Value::known(Fp::from(3)), constant);
+
+    assert_sound(4, &circuit, vec![vec![constant, Fp::from(27 + 3 + 5)]]);
+}
@@ -1,17 +1,21 @@
 use std::{marker::PhantomData, process::ChildStderr};
 use halo2_proofs::{arithmetic::FieldExt, circuit::{*, self}, plonk::*, poly::Rotation};
 
+use crate::soundness::min_k;
+
 #[derive(Debug, Clone)]
 // ここでテーブルのcolumnの一覧を書いていきます。
-struct FibonacciConfig {
+pub struct FibonacciConfig {
     pub col_a: Column<Advice>,
     pub col_b: Column<Advice>,
     pub col_c: Column<Advice>,
     pub selector: Selector,
     pub instance: Column<Instance>,
+    // seedを回路の定数にするとき用
+    pub constant: Column<Fixed>,
 }
 #[derive(Debug, Clone)]
-struct FibonacciChip<F: FieldExt> {
+pub struct FibonacciChip<F: FieldExt> {
     config: FibonacciConfig,
     _maker: PhantomData<F>
 }
@@ -31,6 +35,7 @@ impl<F: FieldExt> FibonacciChip<F> {
         let col_c = meta.advice_column();
         let selector = meta.selector();
         let instance = meta.instance_column();
+        let constant = meta.fixed_column();
 
         // permutation argumentに含めるか否か
         // selectorは要らんかったわw 当たり前体操
@@ -38,6 +43,7 @@ impl<F: FieldExt> FibonacciChip<F> {
         meta.enable_equality(col_b);
         meta.enable_equality(col_c);
         meta.enable_equality(instance);
+        meta.enable_constant(constant);
 
         // create_gateでクエリするcolumnのcellを指定して、constraintとなる方程式を書きます。
         meta.create_gate("add", |meta|{
@@ -55,16 +61,19 @@ impl<F: FieldExt> FibonacciChip<F> {
             col_c,
             selector,
             instance,
+            constant,
         }
     }
 
     // 1行目の割り当てを書いていく
     // ここでやっていることは、instance columnの1,2にそれぞれ、1番目のrowにa, bに入れていきます。（理解したわ）
     // それで、c cellにa+bの結果を入れていきます。
+    // seedsがSomeなら、instanceではなく定数としてcopyしてくる。
     #[allow(clippy::type_complexity)]
     pub fn assign_first_row(
         &self,
         mut layouter: impl Layouter<F>,
+        seeds: Option<(F, F)>,
     ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>, AssignedCell<F, F>), Error> {
         layouter.assign_region(
             || "first row",
@@ -74,20 +83,27 @@ impl<F: FieldExt> FibonacciChip<F> {
                 // e1, e2でこれがないのは、fiexdをselector列として扱っているからや、理解！
                 self.config.selector.enable(&mut region, 0)?;
 
-                // 追記: 初めの2つの値はPublic input（instance column）にあるのだった
-                let a_cell = region.assign_advice_from_instance(
-                    || "f(0)", // わかりやすいように名前
-                    self.config.instance, // どのcolumnから値を受け取るのか
-                    0, // 何row目のなのか
-                    self.config.col_a, // どのadviceにその値を入れるのか
-                    0)?; // col_aの0行目です
-
-                let b_cell = region.assign_advice_from_instance(
-                    || "f(1)",
-                    self.config.instance,
-                    1,
-                    self.config.col_b,
-                    0)?;
+                let (a_cell, b_cell) = match seeds {
+                    // 追記: 初めの2つの値はPublic input（instance column）にあるのだった
+                    None => (
+                        region.assign_advice_from_instance(
+                            || "f(0)", // わかりやすいように名前
+                            self.config.instance, // どのcolumnから値を受け取るのか
+                            0, // 何row目のなのか
+                            self.config.col_a, // どのadviceにその値を入れるのか
+                            0)?, // col_aの0行目です
+                        region.assign_advice_from_instance(
+                            || "f(1)",
+                            self.config.instance,
+                            1,
+                            self.config.col_b,
+                            0)?,
+                    ),
+                    Some((a, b)) => (
+                        region.assign_advice_from_constant(|| "f(0)", self.config.col_a, 0, a)?,
+                        region.assign_advice_from_constant(|| "f(1)", self.config.col_b, 0, b)?,
+                    ),
+                };
 
                 let c_cell = region.assign_advice(
                     || "a + b",
@@ -151,15 +167,57 @@ impl<F: FieldExt> FibonacciChip<F> {
     }
 }
 
-#[derive(Default)]
-struct MyCircuit<F>(PhantomData<F>);
+/// Proves `F(n)` with one region per term.
+///
+/// Without `seeds`, `F(0)` and `F(1)` are read from instance rows 0 and 1 and
+/// `F(n)` is exposed at row 2. With `seeds`, they are constants of the circuit
+/// and `F(n)` is the only public input.
+#[derive(Debug, Clone)]
+pub struct MyCircuit<F> {
+    n: usize,
+    seeds: Option<(F, F)>,
+}
+
+// 今までどおりF(9)まで
+impl<F> Default for MyCircuit<F> {
+    fn default() -> Self {
+        Self { n: 9, seeds: None }
+    }
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    /// The first row already holds `F(2) = a + b`, so synthesis fails with
+    /// `Error::Synthesis` if `n` is below 2.
+    pub fn new(n: usize, seeds: Option<(F, F)>) -> Self {
+        Self { n, seeds }
+    }
+
+    /// The public inputs for the seeds `a` and `b`.
+    pub fn instance(&self, a: F, b: F) -> Vec<F> {
+        let out = crate::e3_fibonacci_ex2::fibonacci(a, b, self.n);
+        match self.seeds {
+            None => vec![a, b, out],
+            Some(_) => vec![out],
+        }
+    }
+
+    /// The smallest `k` whose usable rows fit the `n - 1` regions.
+    ///
+    /// Running with a smaller `k` fails with `Error::NotEnoughRowsAvailable`.
+    pub fn min_k(&self) -> u32 {
+        let mut meta = ConstraintSystem::<F>::default();
+        Self::configure(&mut meta);
+        // 1 regionが1行で、F(2)からF(n)まで
+        min_k(self.n.max(2) - 1, &meta)
+    }
+}
 
 impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
     type Config = FibonacciConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        self.clone()
     }
 
     // custome gateでっせ。
@@ -174,18 +232,26 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
         mut layouter: impl Layouter<F>
     ) -> Result<(), Error> {
         let chip = FibonacciChip::construct(config);
+        // 最初の行がF(2)なので、それより前の項は出せない
+        if self.n < 2 {
+            return Err(Error::Synthesis);
+        }
 
         // row 0（1番最初のrow）の割り当てを実際に行う
-        let (_, mut prev_b, mut prev_c) = chip.assign_first_row(layouter.namespace(|| "first row"))?;
+        let (_, mut prev_b, mut prev_c) =
+            chip.assign_first_row(layouter.namespace(|| "first row"), self.seeds)?;
         // 2行目以降のわりあて
-        for _i in 3..10 {
+        // regionは1行ずつ下に積まれるので、2^kに収まらないとError::NotEnoughRowsAvailableになる
+        for _i in 3..=self.n {
             let c_cell = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
             // ここでpermutation argumentの割り当て
             prev_b = prev_c;
             prev_c = c_cell;
         }
         // instance columnの2 rowに最後のrowの値を移します。
-        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)?;
+        // seedsが定数のときはinstanceは出力だけなので0 row
+        let row = if self.seeds.is_some() { 0 } else { 2 };
+        chip.expose_public(layouter.namespace(|| "out"), &prev_c, row)?;
 
         Ok(())
     }
@@ -202,13 +268,60 @@ fn e3_fibonacci_ex1() {
     // 1..10でフィボナッチした時の答えっす。
     let out = Fp::from(55);
 
-    let circuit = MyCircuit(PhantomData);
+    let circuit = MyCircuit::default();
 
     let public_input = vec![a, b, out];
     // public_inputとtableでできたものが一致するかのMock検証
     let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
     prover.assert_satisfied();
 }
+#[test]
+fn e3_fibonacci_ex1_soundness() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::pasta::Fp;
+
+    let circuit = MyCircuit::<Fp>::default();
+    assert_sound(4, &circuit, vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]]);
+
+    let circuit = MyCircuit::new(9, Some((Fp::from(1), Fp::from(1))));
+    assert_sound(4, &circuit, vec![vec![Fp::from(55)]]);
+}
+
+#[test]
+fn e3_fibonacci_ex1_lengths() {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let (a, b) = (Fp::from(2), Fp::from(5));
+    for n in [2, 3, 9, 20] {
+        for seeds in [None, Some((a, b))] {
+            let circuit = MyCircuit::new(n, seeds);
+            let instance = circuit.instance(a, b);
+            let prover = MockProver::run(circuit.min_k(), &circuit, vec![instance]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    // 定数のseedが違えば、同じ出力は通らない
+    let circuit = MyCircuit::new(9, Some((Fp::from(1), Fp::from(2))));
+    let prover = MockProver::run(4, &circuit, vec![vec![Fp::from(55)]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // F(2)より前の項は作れない
+    let circuit = MyCircuit::new(1, None);
+    assert!(matches!(
+        MockProver::run(circuit.min_k(), &circuit, vec![circuit.instance(a, b)]),
+        Err(Error::Synthesis)
+    ));
+
+    // 2^kに収まらないnはsynthesizeで弾かれる
+    let circuit = MyCircuit::new(20, None);
+    let instance = circuit.instance(a, b);
+    assert!(matches!(
+        MockProver::run(circuit.min_k() - 1, &circuit, vec![instance]),
+        Err(Error::NotEnoughRowsAvailable { .. })
+    ));
+}
+
 // cargo test --features "dev-graph" e3_fibonacci_ex1_plot
 #[cfg(feature = "dev-graph")]
 #[test]
@@ -220,7 +333,7 @@ fn e3_fibonacci_ex1_plot() {
     root.fill(&WHITE).unwrap();
     let root = root.titled("e3_fibonacci_ex1_plot", ("sans-serif", 60)).unwrap();
 
-    let circuit = MyCircuit::<Fp>(PhantomData);
+    let circuit = MyCircuit::<Fp>::default();
     halo2_proofs::dev::CircuitLayout::default()
         .render(4, &circuit, &root)
         .unwrap();
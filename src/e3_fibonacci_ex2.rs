@@ -1,21 +1,25 @@
 use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
 use std::{marker::PhantomData, process::ChildStderr};
 
+use crate::soundness::min_k;
+
 #[derive(Debug, Clone)]
-struct FibonacciConfig {
+pub struct FibonacciConfig {
     advice: Column<Advice>,
     selector: Selector,
     instance: Column<Instance>,
+    // seedを回路の定数にするとき用
+    constant: Column<Fixed>,
 }
 
 #[derive(Debug, Clone)]
-struct FibonacciChip<F: FieldExt> {
+pub struct FibonacciChip<F: FieldExt> {
     config: FibonacciConfig,
     _maker: PhantomData<F>,
 }
 
 impl<F: FieldExt> FibonacciChip<F> {
-    pub fn constract(config: FibonacciConfig) -> Self {
+    pub fn construct(config: FibonacciConfig) -> Self {
         Self {
             config,
             _maker: PhantomData,
@@ -28,9 +32,11 @@ impl<F: FieldExt> FibonacciChip<F> {
         instance: Column<Instance>,
     )-> FibonacciConfig {
         let selector = meta.selector();
+        let constant = meta.fixed_column();
         // permutationに含める
         meta.enable_equality(advice);
         meta.enable_equality(instance);
+        meta.enable_constant(constant);
 
         meta.create_gate("add", |meta| {
             // advice | selector
@@ -52,42 +58,51 @@ impl<F: FieldExt> FibonacciChip<F> {
             advice,
             selector,
             instance,
+            constant,
         }
     }
     // cellのassignを書いていきます。
+    // F(0), F(1) をrow 0, 1に置いて、row nのF(n)を返す。
+    // seedsがNoneならinstanceの0, 1行目から、Someなら定数としてcopyしてくる。
     pub fn assign(
         &self,
         mut layouter: impl Layouter<F>,
-        nrows: usize,
+        n: usize,
+        seeds: Option<(F, F)>,
     ) -> Result<AssignedCell<F, F>, Error> {
         layouter.assign_region(
             || "entire fibonacci table",
             |mut region| {
-                // 2行分のselectorを有効化しています
-                // add gateを有効化しているということです
-                self.config.selector.enable(&mut region, 0)?;
-                self.config.selector.enable(&mut region, 1)?;
-            
-                let mut a_cell = region.assign_advice_from_instance(
-                    || "1", // instanceにある1を持ってくる
-                    self.config.instance, // instance列からデス
-                    0, // 0行目からです
-                    self.config.advice, // advice columnからです
-                    0, // adviceの0行目です。
-                )?;
-
-                let mut b_cell = region.assign_advice_from_instance(
-                    || "1",
-                    self.config.instance,
-                    1,
-                    self.config.advice,
-                    1, // adviceの1行目です。
-                )?;
-
-                for row in 2..nrows {
-                    if row < nrows - 2 {
-                        self.config.selector.enable(&mut region, row)?;
-                    }
+                let (mut a_cell, mut b_cell) = match seeds {
+                    None => (
+                        region.assign_advice_from_instance(
+                            || "f(0)", // instanceにある値を持ってくる
+                            self.config.instance, // instance列からデス
+                            0, // 0行目からです
+                            self.config.advice, // advice columnからです
+                            0, // adviceの0行目です。
+                        )?,
+                        region.assign_advice_from_instance(
+                            || "f(1)",
+                            self.config.instance,
+                            1,
+                            self.config.advice,
+                            1, // adviceの1行目です。
+                        )?,
+                    ),
+                    Some((a, b)) => (
+                        region.assign_advice_from_constant(|| "f(0)", self.config.advice, 0, a)?,
+                        region.assign_advice_from_constant(|| "f(1)", self.config.advice, 1, b)?,
+                    ),
+                };
+
+                if n == 0 {
+                    return Ok(a_cell);
+                }
+
+                for row in 2..=n {
+                    // add gateはrow-2, row-1, rowをみるので、row-2でselectorを有効化する
+                    self.config.selector.enable(&mut region, row - 2)?;
 
                     let c_cell = region.assign_advice(
                         || "advice",
@@ -116,15 +131,60 @@ impl<F: FieldExt> FibonacciChip<F> {
     }
 }
 
-#[derive(Default)]
-struct MyCircuit<F>(PhantomData<F>);
+/// `F(n)` of the sequence `F(0) = a`, `F(1) = b`, `F(i + 2) = F(i) + F(i + 1)`.
+pub fn fibonacci<F: FieldExt>(a: F, b: F, n: usize) -> F {
+    let (mut a, mut b) = (a, b);
+    for _ in 0..n {
+        let c = a + b;
+        a = b;
+        b = c;
+    }
+    a
+}
+
+/// Proves the `n`-th Fibonacci term, using one row per term.
+///
+/// Without `seeds`, `F(0)` and `F(1)` are read from instance rows 0 and 1 and
+/// `F(n)` is exposed at row 2. With `seeds`, they are constants of the circuit
+/// and `F(n)` is the only public input.
+#[derive(Debug, Clone)]
+pub struct MyCircuit<F> {
+    n: usize,
+    seeds: Option<(F, F)>,
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    pub fn new(n: usize, seeds: Option<(F, F)>) -> Self {
+        Self { n, seeds }
+    }
+
+    /// The public inputs for the seeds `a` and `b`.
+    pub fn instance(&self, a: F, b: F) -> Vec<F> {
+        let out = fibonacci(a, b, self.n);
+        match self.seeds {
+            None => vec![a, b, out],
+            Some(_) => vec![out],
+        }
+    }
+
+    /// The smallest `k` whose usable rows fit the `n + 1` terms, and at least
+    /// the two seeds.
+    ///
+    /// Running with a smaller `k` fails with `Error::NotEnoughRowsAvailable`.
+    pub fn min_k(&self) -> u32 {
+        let mut meta = ConstraintSystem::<F>::default();
+        Self::configure(&mut meta);
+        // a, bの2行はnが0でも置く
+        min_k((self.n + 1).max(2), &meta)
+    }
+}
 
 impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
     type Config = FibonacciConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        self.clone()
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
@@ -138,11 +198,13 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
         config: Self::Config, 
         mut layouter: impl Layouter<F>
     ) -> Result<(), Error> {
-        let chip = FibonacciChip::constract(config);
+        let chip = FibonacciChip::construct(config);
 
-        let out_cell = chip.assign(layouter.namespace(|| "entire table"), 10)?;
+        // 行が2^kに収まらないときは、ここでError::NotEnoughRowsAvailableになる
+        let out_cell = chip.assign(layouter.namespace(|| "entire table"), self.n, self.seeds)?;
         // 計算結果をexposeでinstance columnに移動する
-        chip.expose_public(layouter.namespace(|| "out"), out_cell, 2)?;
+        let row = if self.seeds.is_some() { 0 } else { 2 };
+        chip.expose_public(layouter.namespace(|| "out"), out_cell, row)?;
 
         Ok(())
     }
@@ -150,7 +212,6 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
 
 #[test]
 fn e3_fibonacci_ex2() {
-    use std::marker::PhantomData;
     use halo2_proofs::{dev::MockProver, pasta::Fp};
 
     let k = 4;
@@ -158,10 +219,69 @@ fn e3_fibonacci_ex2() {
     let b = Fp::from(1); //F[1]
     let out = Fp::from(55); //F[9]
 
-    let circuit = MyCircuit(PhantomData);
+    let circuit = MyCircuit::new(9, None);
 
     let public_input = vec![a, b, out];
 
     let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
     prover.assert_satisfied() 
-}
\ No newline at end of file
+}
+
+#[test]
+fn e3_fibonacci_ex2_soundness() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::pasta::Fp;
+
+    let circuit = MyCircuit::<Fp>::new(9, None);
+    assert_sound(4, &circuit, vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]]);
+
+    let circuit = MyCircuit::new(9, Some((Fp::from(1), Fp::from(1))));
+    assert_sound(4, &circuit, vec![vec![Fp::from(55)]]);
+}
+
+#[test]
+fn e3_fibonacci_ex2_lengths() {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let (a, b) = (Fp::from(2), Fp::from(5));
+    for n in [0, 1, 2, 3, 9, 20] {
+        for seeds in [None, Some((a, b))] {
+            let circuit = MyCircuit::new(n, seeds);
+            let instance = circuit.instance(a, b);
+            let prover = MockProver::run(circuit.min_k(), &circuit, vec![instance]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+    // 2, 5, 7, 12
+    assert_eq!(fibonacci(a, b, 3), Fp::from(12));
+
+    // 定数のseedが違えば、同じ出力は通らない
+    let circuit = MyCircuit::new(9, Some((Fp::from(1), Fp::from(2))));
+    let prover = MockProver::run(4, &circuit, vec![vec![Fp::from(55)]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 2^kに収まらないnはsynthesizeで弾かれる
+    let circuit = MyCircuit::new(20, None);
+    let instance = circuit.instance(a, b);
+    assert!(matches!(
+        MockProver::run(circuit.min_k() - 1, &circuit, vec![instance]),
+        Err(Error::NotEnoughRowsAvailable { .. })
+    ));
+}
+
+#[test]
+fn e3_fibonacci_ex2_1000th_term() {
+    use crate::prover::{prove, setup, verify};
+    use halo2_proofs::pasta::Fp;
+
+    let one = Fp::from(1);
+    let circuit = MyCircuit::new(1000, Some((one, one)));
+    let k = circuit.min_k();
+    assert_eq!(k, 10);
+
+    let instance = circuit.instance(one, one);
+    let (params, pk) = setup(k, &circuit).unwrap();
+    let proof = prove(&params, &pk, circuit, &[&instance]).unwrap();
+    assert!(verify(&params, pk.get_vk(), &proof, &[&instance]).is_ok());
+    assert!(verify(&params, pk.get_vk(), &proof, &[&[instance[0] + one]]).is_err());
+}
@@ -0,0 +1,429 @@
+use crate::soundness::min_k;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+// 行列の累乗でO(log n)行のFibonacci。
+//
+// G(0) = 0, G(1) = 1 の標準のFibonacciについて
+//
+//   M = [[1, 1], [1, 0]],  M^k = [[G(k + 1), G(k)], [G(k), G(k - 1)]]
+//
+// M^kは (G(k), G(k + 1)) だけで決まるので、この2つを持って2乗していく。
+//
+//   M^2k:     G(2k)     = G(k) * (2 * G(k + 1) - G(k))
+//             G(2k + 1) = G(k)^2 + G(k + 1)^2
+//   M^(2k+1): さらにMを掛ける、つまり (G(2k + 1), G(2k) + G(2k + 1))
+//
+// nを上のbitから1つずつ読んで、k -> 2k + bit と進める。
+// seedが F(0) = a, F(1) = b のときは F(n) = a * G(n - 1) + b * G(n)。
+//
+//  row  | bit | k   | f0      | f1          |
+//  -----+-----+-----+---------+-------------+
+//   0   |     | 0   | 0       | 1           |  q_first, q_step
+//   1   | b_0 | k_1 | G(k_1)  | G(k_1 + 1)  |  q_step
+//   ..  |     |     |         |             |
+//   L   | b_L | n   | G(n)    | G(n + 1)    |  k -> instance
+//   L+1 | a   | b   | F(n)    |             |  q_out, a, b, f0 -> instance
+
+#[derive(Debug, Clone)]
+pub struct FibonacciMatrixConfig {
+    bit: Column<Advice>,
+    k: Column<Advice>,
+    f0: Column<Advice>,
+    f1: Column<Advice>,
+    q_first: Selector,
+    q_step: Selector,
+    q_out: Selector,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FibonacciMatrixChip<F: FieldExt> {
+    config: FibonacciMatrixConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FibonacciMatrixChip<F> {
+    pub fn construct(config: FibonacciMatrixConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FibonacciMatrixConfig {
+        let bit = meta.advice_column();
+        let k = meta.advice_column();
+        let f0 = meta.advice_column();
+        let f1 = meta.advice_column();
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+        let q_out = meta.selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(bit);
+        meta.enable_equality(k);
+        meta.enable_equality(f0);
+        meta.enable_equality(instance);
+
+        meta.create_gate("identity", |meta| {
+            let q = meta.query_selector(q_first);
+            let k = meta.query_advice(k, Rotation::cur());
+            let f0 = meta.query_advice(f0, Rotation::cur());
+            let f1 = meta.query_advice(f1, Rotation::cur());
+
+            // M^0 = I
+            Constraints::with_selector(
+                q,
+                [
+                    ("k = 0", k),
+                    ("G(0) = 0", f0),
+                    ("G(1) = 1", f1 - Expression::Constant(F::one())),
+                ],
+            )
+        });
+
+        meta.create_gate("square and multiply", |meta| {
+            let q = meta.query_selector(q_step);
+            let bit = meta.query_advice(bit, Rotation::next());
+            let k_cur = meta.query_advice(k, Rotation::cur());
+            let k_next = meta.query_advice(k, Rotation::next());
+            let f0_cur = meta.query_advice(f0, Rotation::cur());
+            let f1_cur = meta.query_advice(f1, Rotation::cur());
+            let f0_next = meta.query_advice(f0, Rotation::next());
+            let f1_next = meta.query_advice(f1, Rotation::next());
+
+            let one = Expression::Constant(F::one());
+            let two = Expression::Constant(F::from(2u64));
+
+            // M^kを2乗する
+            let even = f0_cur.clone() * (two.clone() * f1_cur.clone() - f0_cur.clone());
+            let odd = f0_cur.clone() * f0_cur + f1_cur.clone() * f1_cur;
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("boolean", bit.clone() * (one - bit.clone())),
+                    ("k -> 2k + bit", k_next - (two * k_cur + bit.clone())),
+                    // bitが1ならMを掛けて1つずらす
+                    (
+                        "G(k')",
+                        f0_next - (even.clone() + bit.clone() * (odd.clone() - even.clone())),
+                    ),
+                    ("G(k' + 1)", f1_next - (odd + bit * even)),
+                ],
+            )
+        });
+
+        meta.create_gate("seeds", |meta| {
+            let q = meta.query_selector(q_out);
+            let a = meta.query_advice(bit, Rotation::cur());
+            let b = meta.query_advice(k, Rotation::cur());
+            let out = meta.query_advice(f0, Rotation::cur());
+            let g_n = meta.query_advice(f0, Rotation::prev());
+            let g_n_plus_one = meta.query_advice(f1, Rotation::prev());
+
+            // F(n) = a * G(n - 1) + b * G(n)
+            vec![q * (out - (a * (g_n_plus_one - g_n.clone()) + b * g_n))]
+        });
+
+        FibonacciMatrixConfig {
+            bit,
+            k,
+            f0,
+            f1,
+            q_first,
+            q_step,
+            q_out,
+            instance,
+        }
+    }
+
+    /// Reads `n` bit by bit, most significant first, and returns the cells
+    /// holding `n` and `F(n)`.
+    ///
+    /// The seeds `F(0)` and `F(1)` are read from instance rows 0 and 1.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num_bits: usize,
+        n: Value<u64>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+        // nはu64なので、65ビット以上読むとシフトがあふれる
+        if num_bits > 64 {
+            return Err(Error::Synthesis);
+        }
+        n.error_if_known_and(|n| num_bits < 64 && n >> num_bits != 0)?;
+
+        layouter.assign_region(
+            || "fibonacci matrix",
+            |mut region| {
+                config.q_first.enable(&mut region, 0)?;
+                let mut k =
+                    region.assign_advice(|| "k", config.k, 0, || Value::known(F::zero()))?;
+                let mut f0 =
+                    region.assign_advice(|| "G(0)", config.f0, 0, || Value::known(F::zero()))?;
+                let mut f1 =
+                    region.assign_advice(|| "G(1)", config.f1, 0, || Value::known(F::one()))?;
+
+                for row in 1..=num_bits {
+                    config.q_step.enable(&mut region, row - 1)?;
+
+                    let bit = n.map(|n| (n >> (num_bits - row)) & 1 == 1);
+                    region.assign_advice(
+                        || "bit",
+                        config.bit,
+                        row,
+                        || bit.map(|bit| if bit { F::one() } else { F::zero() }),
+                    )?;
+
+                    let (g, g_next) = (f0.value().copied(), f1.value().copied());
+                    let even = g.zip(g_next).map(|(g, g_next)| g * (g_next.double() - g));
+                    let odd = g
+                        .zip(g_next)
+                        .map(|(g, g_next)| g.square() + g_next.square());
+                    let (next0, next1) = even
+                        .zip(odd)
+                        .zip(bit)
+                        .map(
+                            |((even, odd), bit)| {
+                                if bit {
+                                    (odd, even + odd)
+                                } else {
+                                    (even, odd)
+                                }
+                            },
+                        )
+                        .unzip();
+
+                    k = region.assign_advice(
+                        || "k",
+                        config.k,
+                        row,
+                        || n.map(|n| F::from(n >> (num_bits - row))),
+                    )?;
+                    f0 = region.assign_advice(|| "G(k)", config.f0, row, || next0)?;
+                    f1 = region.assign_advice(|| "G(k + 1)", config.f1, row, || next1)?;
+                }
+
+                let row = num_bits + 1;
+                config.q_out.enable(&mut region, row)?;
+                let a = region.assign_advice_from_instance(
+                    || "f(0)",
+                    config.instance,
+                    0,
+                    config.bit,
+                    row,
+                )?;
+                let b = region.assign_advice_from_instance(
+                    || "f(1)",
+                    config.instance,
+                    1,
+                    config.k,
+                    row,
+                )?;
+                let out = region.assign_advice(
+                    || "F(n)",
+                    config.f0,
+                    row,
+                    || {
+                        a.value().copied() * (f1.value().copied() - f0.value())
+                            + b.value().copied() * f0.value()
+                    },
+                )?;
+
+                Ok((k, out))
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Proves `F(n) = out` with `O(num_bits)` rows, for any `n < 2^num_bits`.
+///
+/// The public inputs are `[F(0), F(1), n, F(n)]`; only `num_bits` is part of
+/// the circuit shape, so one key covers every `n` of that width.
+#[derive(Debug, Clone)]
+pub struct FibonacciMatrixCircuit<F> {
+    num_bits: usize,
+    n: Value<u64>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FibonacciMatrixCircuit<F> {
+    pub fn new(num_bits: usize, n: Value<u64>) -> Self {
+        Self {
+            num_bits,
+            n,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The public inputs for the seeds `a` and `b`.
+    ///
+    /// Uses the fast-doubling formulas, so this is cheap even for `n = 2^60`.
+    pub fn instance(a: F, b: F, n: u64) -> Vec<F> {
+        let (g, g_next) = (0..64).rev().fold((F::zero(), F::one()), |(g, g_next), i| {
+            let even = g * (g_next.double() - g);
+            let odd = g.square() + g_next.square();
+            if (n >> i) & 1 == 1 {
+                (odd, even + odd)
+            } else {
+                (even, odd)
+            }
+        });
+        vec![a, b, F::from(n), a * (g_next - g) + b * g]
+    }
+
+    /// The smallest `k` whose usable rows fit the `num_bits + 2` rows.
+    pub fn min_k(&self) -> u32 {
+        let mut meta = ConstraintSystem::<F>::default();
+        Self::configure(&mut meta);
+        min_k(self.num_bits + 2, &meta)
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for FibonacciMatrixCircuit<F> {
+    type Config = FibonacciMatrixConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.num_bits, Value::unknown())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FibonacciMatrixChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FibonacciMatrixChip::construct(config);
+
+        let (n, out) = chip.assign(layouter.namespace(|| "table"), self.num_bits, self.n)?;
+        chip.expose_public(layouter.namespace(|| "n"), &n, 2)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 3)
+    }
+}
+
+#[test]
+fn e3_fibonacci_matrix() {
+    use crate::e3_fibonacci_ex2::fibonacci;
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let (a, b) = (Fp::from(1), Fp::from(1));
+    let num_bits = 5;
+
+    for n in [0, 1, 2, 9, 16, 31] {
+        let circuit = FibonacciMatrixCircuit::new(num_bits, Value::known(n));
+        let k = circuit.min_k();
+        let instance = FibonacciMatrixCircuit::instance(a, b, n);
+        assert_eq!(instance[3], fibonacci(a, b, n as usize), "n = {}", n);
+
+        let prover = MockProver::run(k, &circuit, vec![instance.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()), "n = {}", n);
+
+        let mut wrong = instance;
+        wrong[3] += Fp::one();
+        let prover = MockProver::run(k, &circuit, vec![wrong]).unwrap();
+        assert!(
+            prover.verify().is_err(),
+            "n = {} accepted a wrong output",
+            n
+        );
+    }
+
+    // F(0) = 1, F(1) = 1 の10番目 = 55
+    let circuit = FibonacciMatrixCircuit::new(num_bits, Value::known(9));
+    let instance = vec![a, b, Fp::from(9), Fp::from(55)];
+    assert_sound(circuit.min_k(), &circuit, vec![instance]);
+
+    // num_bitsに収まらないnは作れない
+    let circuit = FibonacciMatrixCircuit::new(num_bits, Value::known(32));
+    assert!(matches!(
+        MockProver::run(
+            circuit.min_k(),
+            &circuit,
+            vec![FibonacciMatrixCircuit::instance(a, b, 32)]
+        ),
+        Err(Error::Synthesis)
+    ));
+
+    // u64のnは64ビットまでしか読めない
+    let circuit = FibonacciMatrixCircuit::new(65, Value::known(9));
+    assert!(matches!(
+        MockProver::run(
+            circuit.min_k(),
+            &circuit,
+            vec![FibonacciMatrixCircuit::instance(a, b, 9)]
+        ),
+        Err(Error::Synthesis)
+    ));
+}
+
+#[test]
+fn e3_fibonacci_matrix_2_pow_20() {
+    use crate::e3_fibonacci_ex2::{fibonacci, MyCircuit};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let n = 1 << 20;
+    let (a, b) = (Fp::from(1), Fp::from(1));
+    let circuit = FibonacciMatrixCircuit::new(21, Value::known(n));
+    let instance = FibonacciMatrixCircuit::instance(a, b, n);
+    assert_eq!(instance[3], fibonacci(a, b, n as usize));
+
+    // ex2は1項1行なので2^20行を超えるが、こちらは23行で済む
+    let k = circuit.min_k();
+    assert_eq!(k, 5);
+    assert_eq!(MyCircuit::<Fp>::new(n as usize, None).min_k(), 21);
+
+    let prover = MockProver::run(k, &circuit, vec![instance]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+// 行数と証明時間をex1, ex2と比べる
+// cargo test --release e3_fibonacci_matrix_compare -- --ignored --nocapture
+#[test]
+#[ignore]
+fn e3_fibonacci_matrix_compare() {
+    use crate::e3_fibonacci_ex1::MyCircuit as MultiColumn;
+    use crate::e3_fibonacci_ex2::MyCircuit as SingleColumn;
+    use crate::report::CostReport;
+    use halo2_proofs::pasta::Fp;
+
+    let (a, b) = (Fp::from(1), Fp::from(1));
+
+    println!("{}", CostReport::header());
+    for n in [9u64, 1 << 8, 1 << 12, 1 << 16] {
+        let multi = MultiColumn::<Fp>::new(n as usize, None);
+        let instance = multi.instance(a, b);
+        let report =
+            CostReport::measure("ex1", n as usize, multi.min_k(), multi, &instance).unwrap();
+        println!("{}", report);
+
+        let single = SingleColumn::<Fp>::new(n as usize, None);
+        let instance = single.instance(a, b);
+        let report =
+            CostReport::measure("ex2", n as usize, single.min_k(), single, &instance).unwrap();
+        println!("{}", report);
+
+        let num_bits = 64 - n.leading_zeros() as usize;
+        let matrix = FibonacciMatrixCircuit::new(num_bits, Value::known(n));
+        let instance = FibonacciMatrixCircuit::instance(a, b, n);
+        let report =
+            CostReport::measure("matrix", n as usize, matrix.min_k(), matrix, &instance).unwrap();
+        println!("{}", report);
+    }
+}
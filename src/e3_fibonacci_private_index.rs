@@ -0,0 +1,307 @@
+use crate::soundness::min_k;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+// nを隠したままF(n) = yを示すFibonacci。
+// テーブルは常にmax_nまで埋めて、activeのフラグがnの次の行で0に落ちる。
+// accにはフラグが落ちた行のF(i)だけが足し込まれるので、最後のaccがF(n)になる。
+//
+//  row  | fib  | active | acc  |
+//  -----+------+--------+------+
+//   0   | F(0) |   1    |  0   |  q_first
+//   1   | F(1) |   1    |  0   |
+//   ..  |      |   1    |  0   |
+//   n   | F(n) |   1    |  0   |
+//   n+1 |      |   0    | F(n) |
+//   ..  |      |   0    | F(n) |
+//  max+1|      |   0    | F(n) |  q_last, acc -> instance
+//
+// active[i] - active[i + 1] はフラグが落ちる行でだけ1になる。
+// activeは0か1で、1 -> 0 にしか変わらず、最初が1で最後が0なので、落ちる行はちょうど1つ。
+
+#[derive(Debug, Clone)]
+pub struct PrivateIndexConfig {
+    fib: Column<Advice>,
+    active: Column<Advice>,
+    acc: Column<Advice>,
+    q_fib: Selector,
+    q_step: Selector,
+    q_first: Selector,
+    q_last: Selector,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PrivateIndexChip<F: FieldExt> {
+    config: PrivateIndexConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> PrivateIndexChip<F> {
+    pub fn construct(config: PrivateIndexConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> PrivateIndexConfig {
+        let fib = meta.advice_column();
+        let active = meta.advice_column();
+        let acc = meta.advice_column();
+        let q_fib = meta.selector();
+        let q_step = meta.selector();
+        let q_first = meta.selector();
+        let q_last = meta.selector();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(fib);
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+        // seedは回路の定数
+        meta.enable_constant(constant);
+
+        // e3_fibonacci_ex2と同じ
+        meta.create_gate("fibonacci", |meta| {
+            let q = meta.query_selector(q_fib);
+            let a = meta.query_advice(fib, Rotation::cur());
+            let b = meta.query_advice(fib, Rotation::next());
+            let c = meta.query_advice(fib, Rotation(2));
+            vec![q * (a + b - c)]
+        });
+
+        meta.create_gate("active flag", |meta| {
+            let q = meta.query_selector(q_step);
+            let active_cur = meta.query_advice(active, Rotation::cur());
+            let active_next = meta.query_advice(active, Rotation::next());
+            let one = Expression::Constant(F::one());
+
+            Constraints::with_selector(
+                q,
+                [
+                    // 0か1
+                    (
+                        "boolean",
+                        active_cur.clone() * (one.clone() - active_cur.clone()),
+                    ),
+                    // 一度0になったら1には戻らない
+                    ("non-increasing", active_next * (one - active_cur)),
+                ],
+            )
+        });
+
+        meta.create_gate("accumulate", |meta| {
+            let q = meta.query_selector(q_step);
+            let f = meta.query_advice(fib, Rotation::cur());
+            let active_cur = meta.query_advice(active, Rotation::cur());
+            let active_next = meta.query_advice(active, Rotation::next());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+
+            vec![q * (acc_next - acc_cur - (active_cur - active_next) * f)]
+        });
+
+        meta.create_gate("first row", |meta| {
+            let q = meta.query_selector(q_first);
+            let active = meta.query_advice(active, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("starts active", Expression::Constant(F::one()) - active),
+                    ("starts at zero", acc),
+                ],
+            )
+        });
+
+        meta.create_gate("last row", |meta| {
+            let q = meta.query_selector(q_last);
+            let active = meta.query_advice(active, Rotation::cur());
+            vec![q * active]
+        });
+
+        PrivateIndexConfig {
+            fib,
+            active,
+            acc,
+            q_fib,
+            q_step,
+            q_first,
+            q_last,
+            instance,
+        }
+    }
+
+    /// Lays out the table up to `max_n` and returns the cell holding `F(n)`.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        seeds: (F, F),
+        max_n: usize,
+        n: Value<usize>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        n.error_if_known_and(|n| *n > max_n)?;
+
+        layouter.assign_region(
+            || "fibonacci with private index",
+            |mut region| {
+                // fibはnに関係なくmax_nまで全部埋める
+                // 前の2つを持ち回して、各行のF(row)を1回ずつ計算する
+                let mut fib = vec![seeds.0, seeds.1];
+                region.assign_advice_from_constant(|| "f(0)", config.fib, 0, seeds.0)?;
+                region.assign_advice_from_constant(|| "f(1)", config.fib, 1, seeds.1)?;
+                for row in 2..=max_n {
+                    config.q_fib.enable(&mut region, row - 2)?;
+                    let value = fib[row - 2] + fib[row - 1];
+                    region.assign_advice(|| "fib", config.fib, row, || Value::known(value))?;
+                    fib.push(value);
+                }
+
+                config.q_first.enable(&mut region, 0)?;
+                config.q_last.enable(&mut region, max_n + 1)?;
+
+                let mut acc = Value::known(F::zero());
+                let mut acc_cell =
+                    region.assign_advice(|| "acc", config.acc, 0, || Value::known(F::zero()))?;
+                for row in 0..=max_n + 1 {
+                    let active = n.map(|n| if row <= n { F::one() } else { F::zero() });
+                    region.assign_advice(|| "active", config.active, row, || active)?;
+
+                    if row <= max_n {
+                        config.q_step.enable(&mut region, row)?;
+                        // フラグが落ちる行でだけF(row)を足す
+                        let drop = n.map(|n| row == n);
+                        let f = fib[row];
+                        acc = acc
+                            .zip(drop)
+                            .map(|(acc, drop)| if drop { acc + f } else { acc });
+                        acc_cell = region.assign_advice(|| "acc", config.acc, row + 1, || acc)?;
+                    }
+                }
+
+                Ok(acc_cell)
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Proves knowledge of an `n <= max_n` with `F(n) = y`, where `y` is the only
+/// public input and `n` stays private.
+#[derive(Debug, Clone)]
+pub struct PrivateIndexCircuit<F> {
+    seeds: (F, F),
+    max_n: usize,
+    n: Value<usize>,
+}
+
+impl<F: FieldExt> PrivateIndexCircuit<F> {
+    pub fn new(seeds: (F, F), max_n: usize, n: Value<usize>) -> Self {
+        Self { seeds, max_n, n }
+    }
+
+    /// The smallest `k` whose usable rows fit the `max_n + 2` rows of the table.
+    pub fn min_k(&self) -> u32 {
+        let mut meta = ConstraintSystem::<F>::default();
+        Self::configure(&mut meta);
+        min_k(self.max_n + 2, &meta)
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for PrivateIndexCircuit<F> {
+    type Config = PrivateIndexConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.seeds, self.max_n, Value::unknown())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PrivateIndexChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = PrivateIndexChip::construct(config);
+
+        let out = chip.assign(
+            layouter.namespace(|| "table"),
+            self.seeds,
+            self.max_n,
+            self.n,
+        )?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)
+    }
+}
+
+#[test]
+fn e3_fibonacci_private_index() {
+    use crate::e3_fibonacci_ex2::fibonacci;
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let seeds = (Fp::from(1), Fp::from(1));
+    let max_n = 12;
+    let k = PrivateIndexCircuit::new(seeds, max_n, Value::unknown()).min_k();
+
+    for n in [0, 1, 5, 9, max_n] {
+        let circuit = PrivateIndexCircuit::new(seeds, max_n, Value::known(n));
+        let y = fibonacci(seeds.0, seeds.1, n);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![y]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()), "n = {}", n);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![y + Fp::one()]]).unwrap();
+        assert!(
+            prover.verify().is_err(),
+            "n = {} accepted a wrong output",
+            n
+        );
+    }
+
+    // F(9) = 55 を、9を明かさずに示す
+    let circuit = PrivateIndexCircuit::new(seeds, max_n, Value::known(9));
+    assert_sound(k, &circuit, vec![vec![Fp::from(55)]]);
+
+    // テーブルに収まらないnは作れない
+    let circuit = PrivateIndexCircuit::new(seeds, max_n, Value::known(max_n + 1));
+    assert!(matches!(
+        MockProver::run(k, &circuit, vec![vec![Fp::zero()]]),
+        Err(Error::Synthesis)
+    ));
+}
+
+#[test]
+fn e3_fibonacci_private_index_prove_verify() {
+    use crate::e3_fibonacci_ex2::fibonacci;
+    use crate::prover::{prove, setup, verify};
+    use halo2_proofs::pasta::Fp;
+
+    let seeds = (Fp::from(1), Fp::from(1));
+    let max_n = 30;
+    let k = PrivateIndexCircuit::new(seeds, max_n, Value::unknown()).min_k();
+
+    // keyはnによらないので、同じkeyで別のnの証明が作れる
+    let (params, pk) = setup(k, &PrivateIndexCircuit::new(seeds, max_n, Value::unknown())).unwrap();
+    for n in [7, 20] {
+        let y = [fibonacci(seeds.0, seeds.1, n)];
+        let circuit = PrivateIndexCircuit::new(seeds, max_n, Value::known(n));
+        let proof = prove(&params, &pk, circuit, &[&y]).unwrap();
+        assert!(verify(&params, pk.get_vk(), &proof, &[&y]).is_ok());
+        assert!(verify(&params, pk.get_vk(), &proof, &[&[y[0] + Fp::one()]]).is_err());
+    }
+}
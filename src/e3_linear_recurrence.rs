@@ -0,0 +1,347 @@
+use crate::soundness::min_k;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+// e3_fibonacci_ex2のgate a + b - c = 0 を、D次の線形漸化式に一般化したもの。
+//
+//   a[i + D] = c_0 * a[i] + c_1 * a[i + 1] + ... + c_{D-1} * a[i + D - 1]
+//
+// advice | c_0 | c_1 | selector
+//  a[0]  |  1  |  1  |    1
+//  a[1]  |  1  |  1  |    1
+//  a[2]  |     |     |
+//  a[3]  |     |     |
+//
+// 係数はfixed columnに入れるので、同じchipでFibonacci, Lucas, Tribonacci, Pellが書ける。
+
+#[derive(Debug, Clone)]
+pub struct LinearRecurrenceConfig<const D: usize> {
+    advice: Column<Advice>,
+    coefficients: [Column<Fixed>; D],
+    selector: Selector,
+    instance: Column<Instance>,
+    // seedを回路の定数にするとき用
+    constant: Column<Fixed>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearRecurrenceChip<F: FieldExt, const D: usize> {
+    config: LinearRecurrenceConfig<D>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const D: usize> LinearRecurrenceChip<F, D> {
+    pub fn construct(config: LinearRecurrenceConfig<D>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        instance: Column<Instance>,
+    ) -> LinearRecurrenceConfig<D> {
+        let coefficients = [(); D].map(|_| meta.fixed_column());
+        let selector = meta.selector();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("linear recurrence", |meta| {
+            // Rotation(0) .. Rotation(D - 1) が直前のD項、Rotation(D) が次の項
+            let s = meta.query_selector(selector);
+            let next = meta.query_advice(advice, Rotation(D as i32));
+            let sum = (0..D)
+                .map(|j| {
+                    meta.query_fixed(coefficients[j], Rotation::cur())
+                        * meta.query_advice(advice, Rotation(j as i32))
+                })
+                .fold(Expression::Constant(F::zero()), |acc, term| acc + term);
+
+            vec![s * (sum - next)]
+        });
+
+        LinearRecurrenceConfig {
+            advice,
+            coefficients,
+            selector,
+            instance,
+            constant,
+        }
+    }
+
+    /// Lays out `a[0] ..= a[n]` and returns the cell holding `a[n]`.
+    ///
+    /// Without `seeds`, `a[0] .. a[D - 1]` are read from instance rows
+    /// `0 .. D`; otherwise they are constants of the circuit.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        coefficients: [F; D],
+        seeds: Option<[F; D]>,
+        n: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "linear recurrence",
+            |mut region| {
+                let mut window = (0..D)
+                    .map(|i| match seeds {
+                        None => region.assign_advice_from_instance(
+                            || format!("a[{}]", i),
+                            config.instance,
+                            i,
+                            config.advice,
+                            i,
+                        ),
+                        Some(seeds) => region.assign_advice_from_constant(
+                            || format!("a[{}]", i),
+                            config.advice,
+                            i,
+                            seeds[i],
+                        ),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if n < D {
+                    return Ok(window[n].clone());
+                }
+
+                for row in D..=n {
+                    // gateはrow - D から row までをみるので、row - D で有効化する
+                    let start = row - D;
+                    config.selector.enable(&mut region, start)?;
+                    for (column, c) in config.coefficients.iter().zip(coefficients) {
+                        region.assign_fixed(
+                            || "coefficient",
+                            *column,
+                            start,
+                            || Value::known(c),
+                        )?;
+                    }
+
+                    let value = window
+                        .iter()
+                        .zip(coefficients)
+                        .fold(Value::known(F::zero()), |acc, (cell, c)| {
+                            acc + cell.value().map(|v| *v * c)
+                        });
+                    let cell = region.assign_advice(
+                        || format!("a[{}]", row),
+                        config.advice,
+                        row,
+                        || value,
+                    )?;
+
+                    window.remove(0);
+                    window.push(cell);
+                }
+
+                Ok(window.pop().unwrap())
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// `a[n]` of the recurrence `a[i + D] = Σ coefficients[j] * a[i + j]`.
+pub fn linear_recurrence<F: FieldExt, const D: usize>(
+    coefficients: [F; D],
+    seeds: [F; D],
+    n: usize,
+) -> F {
+    let mut window = seeds.to_vec();
+    for _ in 0..n {
+        let next = window
+            .iter()
+            .zip(coefficients)
+            .fold(F::zero(), |acc, (a, c)| acc + *a * c);
+        window.remove(0);
+        window.push(next);
+    }
+    window[0]
+}
+
+/// Proves the `n`-th term of an order-`D` linear recurrence.
+///
+/// Without `seeds`, the `D` seeds are public inputs at rows `0 .. D` and
+/// `a[n]` is exposed at row `D`. With `seeds`, `a[n]` is the only public input.
+#[derive(Debug, Clone)]
+pub struct LinearRecurrenceCircuit<F: FieldExt, const D: usize> {
+    coefficients: [F; D],
+    seeds: Option<[F; D]>,
+    n: usize,
+}
+
+impl<F: FieldExt, const D: usize> LinearRecurrenceCircuit<F, D> {
+    pub fn new(coefficients: [F; D], seeds: Option<[F; D]>, n: usize) -> Self {
+        Self {
+            coefficients,
+            seeds,
+            n,
+        }
+    }
+
+    /// The public inputs for the given seeds.
+    pub fn instance(&self, seeds: [F; D]) -> Vec<F> {
+        let out = linear_recurrence(self.coefficients, seeds, self.n);
+        match self.seeds {
+            None => seeds.iter().copied().chain([out]).collect(),
+            Some(_) => vec![out],
+        }
+    }
+
+    /// The smallest `k` whose usable rows fit the `n + 1` terms.
+    pub fn min_k(&self) -> u32 {
+        let mut meta = ConstraintSystem::<F>::default();
+        Self::configure(&mut meta);
+        min_k(self.n.max(D - 1) + 1, &meta)
+    }
+}
+
+impl<F: FieldExt> LinearRecurrenceCircuit<F, 2> {
+    /// 0, 1, 1, 2, 3, 5, ...
+    pub fn fibonacci(n: usize) -> Self {
+        Self::new([F::one(), F::one()], Some([F::zero(), F::one()]), n)
+    }
+
+    /// 2, 1, 3, 4, 7, 11, ...
+    pub fn lucas(n: usize) -> Self {
+        Self::new([F::one(), F::one()], Some([F::from(2u64), F::one()]), n)
+    }
+
+    /// 0, 1, 2, 5, 12, 29, ... with `a[i + 2] = a[i] + 2 * a[i + 1]`.
+    pub fn pell(n: usize) -> Self {
+        Self::new([F::one(), F::from(2u64)], Some([F::zero(), F::one()]), n)
+    }
+}
+
+impl<F: FieldExt> LinearRecurrenceCircuit<F, 3> {
+    /// 0, 0, 1, 1, 2, 4, 7, 13, ...
+    pub fn tribonacci(n: usize) -> Self {
+        Self::new(
+            [F::one(), F::one(), F::one()],
+            Some([F::zero(), F::zero(), F::one()]),
+            n,
+        )
+    }
+}
+
+impl<F: FieldExt, const D: usize> Circuit<F> for LinearRecurrenceCircuit<F, D> {
+    type Config = LinearRecurrenceConfig<D>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        LinearRecurrenceChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = LinearRecurrenceChip::construct(config);
+
+        let out = chip.assign(
+            layouter.namespace(|| "table"),
+            self.coefficients,
+            self.seeds,
+            self.n,
+        )?;
+        let row = if self.seeds.is_some() { 0 } else { D };
+        chip.expose_public(layouter.namespace(|| "out"), &out, row)
+    }
+}
+
+// 数列ごとに同じチェックをする
+#[cfg(test)]
+fn check_sequence<const D: usize>(
+    circuit: impl Fn(usize) -> LinearRecurrenceCircuit<halo2_proofs::pasta::Fp, D>,
+    expected: &[u64],
+) {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    for (n, expected) in expected.iter().enumerate() {
+        let circuit = circuit(n);
+        let k = circuit.min_k();
+        let out = Fp::from(*expected);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()), "term {}", n);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![out + Fp::one()]]).unwrap();
+        assert!(
+            prover.verify().is_err(),
+            "term {} accepted a wrong output",
+            n
+        );
+    }
+
+    let n = expected.len() - 1;
+    let circuit = circuit(n);
+    assert_sound(circuit.min_k(), &circuit, vec![vec![Fp::from(expected[n])]]);
+}
+
+#[test]
+fn e3_linear_recurrence_sequences() {
+    use halo2_proofs::pasta::Fp;
+
+    check_sequence(
+        LinearRecurrenceCircuit::<Fp, 2>::fibonacci,
+        &[0, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55],
+    );
+    check_sequence(
+        LinearRecurrenceCircuit::<Fp, 2>::lucas,
+        &[2, 1, 3, 4, 7, 11, 18, 29, 47, 76, 123],
+    );
+    check_sequence(
+        LinearRecurrenceCircuit::<Fp, 3>::tribonacci,
+        &[0, 0, 1, 1, 2, 4, 7, 13, 24, 44, 81],
+    );
+    check_sequence(
+        LinearRecurrenceCircuit::<Fp, 2>::pell,
+        &[0, 1, 2, 5, 12, 29, 70, 169, 408, 985, 2378],
+    );
+}
+
+#[test]
+fn e3_linear_recurrence_public_seeds() {
+    use crate::e3_fibonacci_ex2::fibonacci;
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // e3_fibonacci_ex2と同じ、seedと出力がpublicな形
+    let one = Fp::one();
+    let circuit = LinearRecurrenceCircuit::<Fp, 2>::new([one, one], None, 9);
+    let instance = circuit.instance([one, one]);
+    assert_eq!(instance, vec![one, one, fibonacci(one, one, 9)]);
+
+    let k = circuit.min_k();
+    let prover = MockProver::run(k, &circuit, vec![instance.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    assert_sound(k, &circuit, vec![instance]);
+
+    // 係数が違う回路は別の数列になる
+    let pell = LinearRecurrenceCircuit::<Fp, 2>::new([one, Fp::from(2)], None, 9);
+    let prover = MockProver::run(k, &pell, vec![circuit.instance([one, one])]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -18,13 +18,20 @@ use halo2_proofs::{
 
 // これは何のstructかわからない
 #[derive(Debug, Clone)]
-struct RangeConstrained<F:FieldExt, const RANGE: usize>(AssignedCell<Assigned<F>, F>);
+pub struct RangeConstrained<F:FieldExt, const RANGE: usize>(AssignedCell<Assigned<F>, F>);
+
+impl<F: FieldExt, const RANGE: usize> RangeConstrained<F, RANGE> {
+    /// The cell holding the value that was checked to be in `0..RANGE`.
+    pub fn cell(&self) -> &AssignedCell<Assigned<F>, F> {
+        &self.0
+    }
+}
 
 // plonkishのtableのcolumnを書く
 // 今回は、Config書いてconstruct関数書いて、Chip書いてみたいなくだりは無くて、
 // Cofigを起点に書いていく
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: FieldExt, const RANGE: usize> {
+pub struct RangeCheckConfig<F: FieldExt, const RANGE: usize> {
     value: Column<Advice>,
     q_range_check: Selector,
     _maker: PhantomData<F>,
@@ -83,13 +90,54 @@ impl<F: FieldExt, const RANGE: usize> RangeCheckConfig<F, RANGE> {
             },
         )
     } 
+
+    /// Checks every value in one region, one row per value.
+    pub fn assign_batch(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[Value<Assigned<F>>],
+    ) -> Result<Vec<RangeConstrained<F, RANGE>>, Error> {
+        // `assign`を何千回も呼ぶとregionも何千個になるので、まとめて置く。
+        assign_rows(layouter, "Assign values", self.q_range_check, self.value, values)
+            .map(|cells| cells.into_iter().map(RangeConstrained).collect())
+    }
+}
+
+/// Assigns `values` to `column` in one region, one row per value, with
+/// `selector` enabled on every row.
+pub(crate) fn assign_rows<F: FieldExt>(
+    mut layouter: impl Layouter<F>,
+    name: &str,
+    selector: Selector,
+    column: Column<Advice>,
+    values: &[Value<Assigned<F>>],
+) -> Result<Vec<AssignedCell<Assigned<F>, F>>, Error> {
+    layouter.assign_region(
+        || name,
+        |mut region| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(offset, value)| {
+                    selector.enable(&mut region, offset)?;
+                    region.assign_advice(|| "value", column, offset, || *value)
+                })
+                .collect()
+        },
+    )
 }
 
 #[derive(Default)]
-struct Mycircuit<F: FieldExt, const RANGE: usize> {
+pub struct Mycircuit<F: FieldExt, const RANGE: usize> {
     value: Value<Assigned<F>>,
 }
 
+impl<F: FieldExt, const RANGE: usize> Mycircuit<F, RANGE> {
+    pub fn new(value: Value<Assigned<F>>) -> Self {
+        Self { value }
+    }
+}
+
 impl<F: FieldExt, const RANGE: usize> Circuit<F> for Mycircuit<F, RANGE> {
     type Config = RangeCheckConfig<F, RANGE>;
     // simplefloorrunnerでは無くて、V1とは何なのか？
@@ -115,6 +163,42 @@ impl<F: FieldExt, const RANGE: usize> Circuit<F> for Mycircuit<F, RANGE> {
     } 
 }
 
+/// Checks every value in `values` with one region.
+#[derive(Default)]
+pub struct BatchCircuit<F: FieldExt, const RANGE: usize> {
+    values: Vec<Value<Assigned<F>>>,
+}
+
+impl<F: FieldExt, const RANGE: usize> BatchCircuit<F, RANGE> {
+    pub fn new(values: Vec<Value<Assigned<F>>>) -> Self {
+        Self { values }
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize> Circuit<F> for BatchCircuit<F, RANGE> {
+    type Config = RangeCheckConfig<F, RANGE>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(vec![Value::unknown(); self.values.len()])
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        RangeCheckConfig::configure(meta, value)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.assign_batch(layouter.namespace(|| "Assign values"), &self.values)?;
+
+        Ok(())
+    }
+}
+
 #[test]
 fn e4_simple_rangecheck() {
     use halo2_proofs::{dev::MockProver, pasta::Fp};
@@ -129,3 +213,27 @@ fn e4_simple_rangecheck() {
     prover.assert_satisfied()
 
 }
+
+#[test]
+fn e4_simple_rangecheck_batch() {
+    use crate::soundness::mock_verify;
+    use halo2_proofs::pasta::Fp;
+    let k = 6;
+    const RANGE: usize = 8;
+
+    let circuit = |values: &[u64]| {
+        BatchCircuit::<Fp, RANGE>::new(
+            values
+                .iter()
+                .map(|v| Value::known(Fp::from(*v).into()))
+                .collect(),
+        )
+    };
+
+    let mut values = (0..50).map(|i| i % RANGE as u64).collect::<Vec<_>>();
+    assert_eq!(mock_verify(k, &circuit(&values), vec![]), Ok(()));
+
+    // 途中の1つだけ範囲外
+    values[30] = RANGE as u64;
+    assert!(mock_verify(k, &circuit(&values), vec![]).is_err());
+}
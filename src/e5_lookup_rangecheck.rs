@@ -6,8 +6,22 @@ use halo2_proofs::{
 };
 use std::marker::PhantomData;
 
+use crate::e4_simple_rangecheck::assign_rows;
+
 mod e5_lookup_table;
-use e5_lookup_table::*;
+pub use e5_lookup_table::*;
+
+mod e5_tagged_rangecheck;
+pub use e5_tagged_rangecheck::*;
+
+mod e5_compare;
+pub use e5_compare::*;
+
+mod e5_table_loader;
+pub use e5_table_loader::*;
+
+mod e5_bitwise;
+pub use e5_bitwise::*;
 
 // This helper checks that the value witnessed in a given cell is within a given range.
 // Depending on the range, this helper uses either a range-check expression (for small ranges)
@@ -20,14 +34,21 @@ use e5_lookup_table::*;
 
 #[derive(Debug, Clone)]
 // A range-constrained value in the circuit produced by the RangeCheckConfig.
-struct RangeConstrained<F: FieldExt, const RANGE: usize>(AssignedCell<Assigned<F>, F>);
+pub struct RangeConstrained<F: FieldExt, const RANGE: usize>(AssignedCell<Assigned<F>, F>);
+
+impl<F: FieldExt, const RANGE: usize> RangeConstrained<F, RANGE> {
+    /// The cell holding the value that was checked to be in `0..RANGE`.
+    pub fn cell(&self) -> &AssignedCell<Assigned<F>, F> {
+        &self.0
+    }
+}
 
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> {
+pub struct RangeCheckConfig<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> {
     q_range_check: Selector,
     q_lookup: Selector,
     value: Column<Advice>,
-    table: RangeTableConfig<F, LOOKUP_RANGE>,
+    pub table: RangeTableConfig<F, LOOKUP_RANGE>,
 }
 
 impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> RangeCheckConfig<F, RANGE, LOOKUP_RANGE> {
@@ -121,14 +142,58 @@ impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> RangeCheckConfi
         )
     }
 
+    // 1つずつだとregionが値の数だけできるので、まとめて置く版。
+    // 1行に1つずつ、selectorも行ごとにenableする。
+
+    /// Checks every value with the `RANGE` gate, all in one region.
+    pub fn assign_simple_batch(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[Value<Assigned<F>>],
+    ) -> Result<Vec<RangeConstrained<F, RANGE>>, Error> {
+        assign_rows(
+            layouter,
+            "Assign values for simple range check",
+            self.q_range_check,
+            self.value,
+            values,
+        )
+        .map(|cells| cells.into_iter().map(RangeConstrained).collect())
+    }
+
+    /// Checks every value against the lookup table, all in one region.
+    pub fn assign_lookup_batch(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[Value<Assigned<F>>],
+    ) -> Result<Vec<RangeConstrained<F, LOOKUP_RANGE>>, Error> {
+        assign_rows(
+            layouter,
+            "Assign values for lookup range check",
+            self.q_lookup,
+            self.value,
+            values,
+        )
+        .map(|cells| cells.into_iter().map(RangeConstrained).collect())
+    }
 }
 
 #[derive(Default)]
-struct MyCircuit<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> {
+pub struct MyCircuit<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> {
     value: Value<Assigned<F>>,
     lookup_value: Value<Assigned<F>>,
 }
 
+impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> MyCircuit<F, RANGE, LOOKUP_RANGE> {
+    /// `value` is checked with the `RANGE` gate, `lookup_value` against the lookup table.
+    pub fn new(value: Value<Assigned<F>>, lookup_value: Value<Assigned<F>>) -> Self {
+        Self {
+            value,
+            lookup_value,
+        }
+    }
+}
+
 impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> Circuit<F> for MyCircuit<F, RANGE, LOOKUP_RANGE> {
     type Config = RangeCheckConfig<F, RANGE, LOOKUP_RANGE>;
     type FloorPlanner = V1;
@@ -161,6 +226,56 @@ impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> Circuit<F> for
     }
 }
 
+/// Checks `values` with the `RANGE` gate and `lookup_values` against the
+/// lookup table, one region each.
+#[derive(Default)]
+pub struct BatchCircuit<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> {
+    values: Vec<Value<Assigned<F>>>,
+    lookup_values: Vec<Value<Assigned<F>>>,
+}
+
+impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> BatchCircuit<F, RANGE, LOOKUP_RANGE> {
+    pub fn new(values: Vec<Value<Assigned<F>>>, lookup_values: Vec<Value<Assigned<F>>>) -> Self {
+        Self {
+            values,
+            lookup_values,
+        }
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> Circuit<F> for BatchCircuit<F, RANGE, LOOKUP_RANGE> {
+    type Config = RangeCheckConfig<F, RANGE, LOOKUP_RANGE>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(
+            vec![Value::unknown(); self.values.len()],
+            vec![Value::unknown(); self.lookup_values.len()],
+        )
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        RangeCheckConfig::configure(meta, value)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.table.load(&mut layouter)?;
+
+        config.assign_simple_batch(layouter.namespace(|| "Assign simple values"), &self.values)?;
+        config.assign_lookup_batch(
+            layouter.namespace(|| "Assign lookup values"),
+            &self.lookup_values,
+        )?;
+
+        Ok(())
+    }
+}
+
 #[test]
 fn e5_lookup_rangecheck() {
     use halo2_proofs::{dev::MockProver, pasta::Fp};
@@ -176,4 +291,38 @@ fn e5_lookup_rangecheck() {
 
     let prover = MockProver::run(k, &circuit, vec![]).unwrap();
     prover.assert_satisfied();
-}
\ No newline at end of file
+}
+
+#[test]
+fn e5_lookup_rangecheck_batch() {
+    use crate::soundness::mock_verify;
+    use halo2_proofs::pasta::Fp;
+
+    let k = 11;
+    const RANGE: usize = 8;
+    const LOOKUP_RANGE: usize = 256;
+
+    let known = |values: &[u64]| {
+        values
+            .iter()
+            .map(|v| Value::known(Fp::from(*v).into()))
+            .collect::<Vec<_>>()
+    };
+    let circuit = |values: &[u64], lookup_values: &[u64]| {
+        BatchCircuit::<Fp, RANGE, LOOKUP_RANGE>::new(known(values), known(lookup_values))
+    };
+
+    // 1000バイトでもregionは1つ
+    let bytes = (0..1000u64).map(|i| (i * 37) % 256).collect::<Vec<_>>();
+    let small = (0..20u64).map(|i| i % 8).collect::<Vec<_>>();
+    assert_eq!(mock_verify(k, &circuit(&small, &bytes), vec![]), Ok(()));
+    assert_eq!(mock_verify(k, &circuit(&[], &[]), vec![]), Ok(()));
+
+    let mut bad_bytes = bytes.clone();
+    bad_bytes[777] = 256;
+    assert!(mock_verify(k, &circuit(&small, &bad_bytes), vec![]).is_err());
+
+    let mut bad_small = small;
+    bad_small[19] = 8;
+    assert!(mock_verify(k, &circuit(&bad_small, &bytes), vec![]).is_err());
+}
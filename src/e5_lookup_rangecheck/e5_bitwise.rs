@@ -0,0 +1,366 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector,
+        TableColumn,
+    },
+    poly::Rotation,
+};
+
+use super::RangeConstrained;
+
+// バイトごとのXOR/AND/ORを、演算1回につきlookup 1回で証明する。
+// テーブルは (op, a, b, a op b) の4列で、a, bは0..RANGEの全部の組。
+//
+//   op  | a | b | out         <- TableColumn
+//  -----+---+---+-----
+//   XOR | 0 | 0 |  0          <- selectorが0の行は (0, 0, 0, 0) を引く
+//   XOR | 0 | 1 |  1
+//   ..  |   |   |
+//   OR  | R-1 | R-1 | R-1
+//
+// XORのタグを0にしておくと (0, 0, 0, 0) がそのまま 0 XOR 0 = 0 の行になる。
+// 回路側のopはfixed columnなので、proverは演算を変えられない。
+//
+//   a | b | out | op | q_lookup
+//  ---+---+-----+----+----------
+//   x | y | x^y |  0 |    1
+//
+// a, bもテーブルの列なので、0..RANGEに入っていなければ落ちる。
+// RANGE = 256 だとテーブルは 3 * 2^16 行なので k >= 18 になる。
+
+/// The operations in a [`BitwiseTableConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitwiseOp {
+    Xor,
+    And,
+    Or,
+}
+
+impl BitwiseOp {
+    pub const ALL: [BitwiseOp; 3] = [BitwiseOp::Xor, BitwiseOp::And, BitwiseOp::Or];
+
+    /// The value of the op column.
+    pub fn tag(self) -> u64 {
+        match self {
+            BitwiseOp::Xor => 0,
+            BitwiseOp::And => 1,
+            BitwiseOp::Or => 2,
+        }
+    }
+
+    pub fn apply(self, a: u64, b: u64) -> u64 {
+        match self {
+            BitwiseOp::Xor => a ^ b,
+            BitwiseOp::And => a & b,
+            BitwiseOp::Or => a | b,
+        }
+    }
+}
+
+/// A lookup table of `(op, a, b, a op b)` for every `a, b` in `0..RANGE`.
+#[derive(Debug, Clone)]
+pub struct BitwiseTableConfig<F: FieldExt, const RANGE: usize> {
+    pub op: TableColumn,
+    pub a: TableColumn,
+    pub b: TableColumn,
+    pub out: TableColumn,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const RANGE: usize> BitwiseTableConfig<F, RANGE> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        assert!(RANGE.is_power_of_two(), "RANGE must be a power of two");
+
+        Self {
+            op: meta.lookup_table_column(),
+            a: meta.lookup_table_column(),
+            b: meta.lookup_table_column(),
+            out: meta.lookup_table_column(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load bitwise table",
+            |mut table| {
+                let mut offset = 0;
+                for op in BitwiseOp::ALL {
+                    for a in 0..RANGE as u64 {
+                        for b in 0..RANGE as u64 {
+                            let row = [
+                                (self.op, op.tag()),
+                                (self.a, a),
+                                (self.b, b),
+                                (self.out, op.apply(a, b)),
+                            ];
+                            for (column, value) in row {
+                                table.assign_cell(
+                                    || "value",
+                                    column,
+                                    offset,
+                                    || Value::known(F::from(value)),
+                                )?;
+                            }
+                            offset += 1;
+                        }
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BitwiseConfig<F: FieldExt, const RANGE: usize> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    out: Column<Advice>,
+    op: Column<Fixed>,
+    q_lookup: Selector,
+    pub table: BitwiseTableConfig<F, RANGE>,
+}
+
+impl<F: FieldExt, const RANGE: usize> BitwiseConfig<F, RANGE> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> Self {
+        let [a, b, out] = advice;
+        let op = meta.fixed_column();
+        let q_lookup = meta.complex_selector();
+        let table = BitwiseTableConfig::configure(meta);
+
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(out);
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let op = meta.query_fixed(op, Rotation::cur());
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            vec![
+                (q.clone() * op, table.op),
+                (q.clone() * a, table.a),
+                (q.clone() * b, table.b),
+                (q * out, table.out),
+            ]
+        });
+
+        Self {
+            a,
+            b,
+            out,
+            op,
+            q_lookup,
+            table,
+        }
+    }
+
+    /// `a ^ b`. Fails to verify unless `a` and `b` are in `0..RANGE`.
+    pub fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<Assigned<F>, F>,
+        b: &AssignedCell<Assigned<F>, F>,
+    ) -> Result<RangeConstrained<F, RANGE>, Error> {
+        self.assign(layouter, BitwiseOp::Xor, a, b)
+    }
+
+    /// `a & b`. Fails to verify unless `a` and `b` are in `0..RANGE`.
+    pub fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<Assigned<F>, F>,
+        b: &AssignedCell<Assigned<F>, F>,
+    ) -> Result<RangeConstrained<F, RANGE>, Error> {
+        self.assign(layouter, BitwiseOp::And, a, b)
+    }
+
+    /// `a | b`. Fails to verify unless `a` and `b` are in `0..RANGE`.
+    pub fn or(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<Assigned<F>, F>,
+        b: &AssignedCell<Assigned<F>, F>,
+    ) -> Result<RangeConstrained<F, RANGE>, Error> {
+        self.assign(layouter, BitwiseOp::Or, a, b)
+    }
+
+    /// `a op b` with one lookup.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: BitwiseOp,
+        a: &AssignedCell<Assigned<F>, F>,
+        b: &AssignedCell<Assigned<F>, F>,
+    ) -> Result<RangeConstrained<F, RANGE>, Error> {
+        layouter.assign_region(
+            || format!("{:?}", op),
+            |mut region| {
+                let offset = 0;
+
+                self.q_lookup.enable(&mut region, offset)?;
+                region.assign_fixed(
+                    || "op",
+                    self.op,
+                    offset,
+                    || Value::known(F::from(op.tag())),
+                )?;
+
+                let a = a.copy_advice(|| "a", &mut region, self.a, offset)?;
+                let b = b.copy_advice(|| "b", &mut region, self.b, offset)?;
+
+                // 範囲外の入力は下位ビットで計算しておく。どうせlookupで落ちる
+                let out = a.value().zip(b.value()).map(|(a, b)| {
+                    let a = a.evaluate().get_lower_128() as u64;
+                    let b = b.evaluate().get_lower_128() as u64;
+                    Assigned::from(F::from(op.apply(a, b)))
+                });
+                region
+                    .assign_advice(|| "out", self.out, offset, || out)
+                    .map(RangeConstrained)
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BitwiseCircuitConfig<F: FieldExt, const RANGE: usize> {
+    bitwise: BitwiseConfig<F, RANGE>,
+    instance: Column<Instance>,
+}
+
+/// Exposes `[a ^ b, a & b, a | b]` for each pair.
+pub struct BitwiseCircuit<F: FieldExt, const RANGE: usize> {
+    pairs: Vec<(Value<F>, Value<F>)>,
+}
+
+impl<F: FieldExt, const RANGE: usize> BitwiseCircuit<F, RANGE> {
+    pub fn new(pairs: Vec<(Value<F>, Value<F>)>) -> Self {
+        Self { pairs }
+    }
+
+    pub fn instance(pairs: &[(u64, u64)]) -> Vec<F> {
+        pairs
+            .iter()
+            .flat_map(|(a, b)| BitwiseOp::ALL.map(|op| F::from(op.apply(*a, *b))))
+            .collect()
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize> Circuit<F> for BitwiseCircuit<F, RANGE> {
+    type Config = BitwiseCircuitConfig<F, RANGE>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(vec![(Value::unknown(), Value::unknown()); self.pairs.len()])
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        BitwiseCircuitConfig {
+            bitwise: BitwiseConfig::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let bitwise = &config.bitwise;
+        bitwise.table.load(&mut layouter)?;
+
+        let mut row = 0;
+        for (a, b) in &self.pairs {
+            let (a, b) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", bitwise.a, 0, || a.map(Assigned::from))?;
+                    let b = region.assign_advice(|| "b", bitwise.b, 0, || b.map(Assigned::from))?;
+                    Ok((a, b))
+                },
+            )?;
+
+            for op in BitwiseOp::ALL {
+                let out = bitwise.assign(layouter.namespace(|| "op"), op, &a, &b)?;
+                layouter.constrain_instance(out.cell().cell(), config.instance, row)?;
+                row += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn e5_bitwise() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // 4ビットならテーブルは 3 * 256 行
+    const RANGE: usize = 16;
+    let k = 10;
+    let circuit = |pairs: &[(u64, u64)]| {
+        BitwiseCircuit::<Fp, RANGE>::new(
+            pairs
+                .iter()
+                .map(|(a, b)| (Value::known(Fp::from(*a)), Value::known(Fp::from(*b))))
+                .collect(),
+        )
+    };
+
+    let pairs = [(0, 0), (0b1010, 0b0110), (15, 0), (15, 15), (3, 12)];
+    let instance = BitwiseCircuit::<Fp, RANGE>::instance(&pairs);
+    let prover = MockProver::run(k, &circuit(&pairs), vec![instance.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // 別の演算の答え: a ^ b の場所に a | b
+    let mut wrong = instance.clone();
+    wrong.swap(3, 5);
+    let prover = MockProver::run(k, &circuit(&pairs), vec![wrong]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 入力がRANGEに入っていない。16 ^ 1 = 17 もテーブルにない
+    let outside = [(16, 1)];
+    let instance = BitwiseCircuit::<Fp, RANGE>::instance(&outside);
+    let prover = MockProver::run(k, &circuit(&outside), vec![instance]).unwrap();
+    assert!(prover.verify().is_err());
+
+    assert_sound(
+        k,
+        &circuit(&pairs[1..3]),
+        vec![BitwiseCircuit::<Fp, RANGE>::instance(&pairs[1..3])],
+    );
+}
+
+// バイトのテーブルは 3 * 2^16 行あるので遅い
+#[test]
+#[ignore]
+fn e5_bitwise_bytes() {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let k = 18;
+    let pairs = [(0x5a, 0xc3), (0xff, 0x0f), (0x80, 0x01)];
+    let circuit = BitwiseCircuit::<Fp, 256>::new(
+        pairs
+            .iter()
+            .map(|(a, b)| (Value::known(Fp::from(*a)), Value::known(Fp::from(*b))))
+            .collect(),
+    );
+    let instance = BitwiseCircuit::<Fp, 256>::instance(&pairs);
+
+    let prover = MockProver::run(k, &circuit, vec![instance]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
@@ -0,0 +1,364 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression, Fixed,
+        Instance, Selector,
+    },
+    poly::Rotation,
+};
+
+use super::{RangeCheckConfig, RangeConstrained, RangeTableConfig};
+
+// 2つのwitnessの大小比較。a, bはどちらもRangeConstrained (0 <= a, b < R) とする。
+//
+//   diff = a - b - or_equal + R * out,  outは0か1,  diffはテーブル (0..R) にある
+//
+// or_equal = 0 のとき out = (a < b)
+//   a < b  なら a - b + R は 1..R に入る、a >= b なら a - b は 0..R に入る
+// or_equal = 1 のとき out = (a <= b)
+//   a <= b なら a - b - 1 + R は 0..R に入る、a > b なら a - b - 1 は 0..R-1 に入る
+//
+// outを逆にするとdiffはR以上か負 (pの近く) になるので、テーブルにない。
+// a, bが0..Rに入っていないと回り込めてしまうので、入力はRangeConstrainedに限る。
+//
+//   lhs | rhs | out | diff | q_compare | or_equal | q_and
+//  -----+-----+-----+------+-----------+----------+-------
+//    a  |  b  | out | diff |     1     |  0 / 1   |
+//    x  |  y  | x*y |      |           |          |   1
+
+#[derive(Debug, Clone)]
+pub struct CompareConfig<F: FieldExt, const LOOKUP_RANGE: usize> {
+    lhs: Column<Advice>,
+    rhs: Column<Advice>,
+    out: Column<Advice>,
+    diff: Column<Advice>,
+    or_equal: Column<Fixed>,
+    q_compare: Selector,
+    q_and: Selector,
+    pub table: RangeTableConfig<F, LOOKUP_RANGE>,
+}
+
+impl<F: FieldExt, const LOOKUP_RANGE: usize> CompareConfig<F, LOOKUP_RANGE> {
+    /// `table` is shared with the `RangeCheckConfig` that produced the inputs.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        table: RangeTableConfig<F, LOOKUP_RANGE>,
+    ) -> Self {
+        let [lhs, rhs, out, diff] = advice;
+        let or_equal = meta.fixed_column();
+        let q_compare = meta.complex_selector();
+        let q_and = meta.selector();
+
+        meta.enable_equality(lhs);
+        meta.enable_equality(rhs);
+        meta.enable_equality(out);
+
+        meta.create_gate("compare", |meta| {
+            let q = meta.query_selector(q_compare);
+            let a = meta.query_advice(lhs, Rotation::cur());
+            let b = meta.query_advice(rhs, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+            let or_equal = meta.query_fixed(or_equal, Rotation::cur());
+
+            let one = Expression::Constant(F::one());
+            let range = Expression::Constant(F::from(LOOKUP_RANGE as u64));
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("boolean", out.clone() * (one - out.clone())),
+                    ("diff", diff - (a - b - or_equal + range * out)),
+                ],
+            )
+        });
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_compare);
+            let diff = meta.query_advice(diff, Rotation::cur());
+
+            vec![(q * diff, table.value)]
+        });
+
+        // 入力はどちらも比較の結果なので、booleanはもう保証されている
+        meta.create_gate("and", |meta| {
+            let q = meta.query_selector(q_and);
+            let x = meta.query_advice(lhs, Rotation::cur());
+            let y = meta.query_advice(rhs, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            vec![q * (out - x * y)]
+        });
+
+        Self {
+            lhs,
+            rhs,
+            out,
+            diff,
+            or_equal,
+            q_compare,
+            q_and,
+            table,
+        }
+    }
+
+    /// A boolean cell constrained to `a < b`.
+    pub fn lt(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &RangeConstrained<F, LOOKUP_RANGE>,
+        b: &RangeConstrained<F, LOOKUP_RANGE>,
+    ) -> Result<AssignedCell<Assigned<F>, F>, Error> {
+        self.compare(layouter, a, b, false)
+    }
+
+    /// A boolean cell constrained to `a <= b`.
+    pub fn le(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &RangeConstrained<F, LOOKUP_RANGE>,
+        b: &RangeConstrained<F, LOOKUP_RANGE>,
+    ) -> Result<AssignedCell<Assigned<F>, F>, Error> {
+        self.compare(layouter, a, b, true)
+    }
+
+    /// A boolean cell constrained to `lo <= a < hi`.
+    pub fn in_interval(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &RangeConstrained<F, LOOKUP_RANGE>,
+        lo: &RangeConstrained<F, LOOKUP_RANGE>,
+        hi: &RangeConstrained<F, LOOKUP_RANGE>,
+    ) -> Result<AssignedCell<Assigned<F>, F>, Error> {
+        let above = self.le(layouter.namespace(|| "lo <= a"), lo, a)?;
+        let below = self.lt(layouter.namespace(|| "a < hi"), a, hi)?;
+
+        layouter.assign_region(
+            || "and",
+            |mut region| {
+                self.q_and.enable(&mut region, 0)?;
+                let x = above.copy_advice(|| "lo <= a", &mut region, self.lhs, 0)?;
+                let y = below.copy_advice(|| "a < hi", &mut region, self.rhs, 0)?;
+                region.assign_advice(
+                    || "lo <= a < hi",
+                    self.out,
+                    0,
+                    || x.value().copied() * y.value().copied(),
+                )
+            },
+        )
+    }
+
+    fn compare(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &RangeConstrained<F, LOOKUP_RANGE>,
+        b: &RangeConstrained<F, LOOKUP_RANGE>,
+        or_equal: bool,
+    ) -> Result<AssignedCell<Assigned<F>, F>, Error> {
+        layouter.assign_region(
+            || if or_equal { "a <= b" } else { "a < b" },
+            |mut region| {
+                self.q_compare.enable(&mut region, 0)?;
+                let offset = F::from(or_equal as u64);
+                region.assign_fixed(|| "or_equal", self.or_equal, 0, || Value::known(offset))?;
+
+                let a = a.cell().copy_advice(|| "a", &mut region, self.lhs, 0)?;
+                let b = b.cell().copy_advice(|| "b", &mut region, self.rhs, 0)?;
+
+                // 0..Rの値なのでu128で比べられる
+                let out = a.value().zip(b.value()).map(|(a, b)| {
+                    let (a, b) = (a.evaluate().get_lower_128(), b.evaluate().get_lower_128());
+                    let out = if or_equal { a <= b } else { a < b };
+                    Assigned::from(F::from(out as u64))
+                });
+                let diff =
+                    a.value().copied() - b.value().copied() - Value::known(Assigned::from(offset))
+                        + out * Value::known(Assigned::from(F::from(LOOKUP_RANGE as u64)));
+
+                region.assign_advice(|| "diff", self.diff, 0, || diff)?;
+                region.assign_advice(|| "out", self.out, 0, || out)
+            },
+        )
+    }
+}
+
+/// Exposes `[a < b, a <= b, lo <= a < hi]`.
+#[derive(Default)]
+pub struct CompareCircuit<F: FieldExt, const LOOKUP_RANGE: usize> {
+    a: Value<Assigned<F>>,
+    b: Value<Assigned<F>>,
+    lo: Value<Assigned<F>>,
+    hi: Value<Assigned<F>>,
+}
+
+impl<F: FieldExt, const LOOKUP_RANGE: usize> CompareCircuit<F, LOOKUP_RANGE> {
+    pub fn new(
+        a: Value<Assigned<F>>,
+        b: Value<Assigned<F>>,
+        lo: Value<Assigned<F>>,
+        hi: Value<Assigned<F>>,
+    ) -> Self {
+        Self { a, b, lo, hi }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompareCircuitConfig<F: FieldExt, const LOOKUP_RANGE: usize> {
+    range_check: RangeCheckConfig<F, 8, LOOKUP_RANGE>,
+    compare: CompareConfig<F, LOOKUP_RANGE>,
+    instance: Column<Instance>,
+}
+
+impl<F: FieldExt, const LOOKUP_RANGE: usize> Circuit<F> for CompareCircuit<F, LOOKUP_RANGE> {
+    type Config = CompareCircuitConfig<F, LOOKUP_RANGE>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        let range_check = RangeCheckConfig::configure(meta, value);
+        meta.enable_equality(value);
+
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let compare = CompareConfig::configure(meta, advice, range_check.table.clone());
+
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        CompareCircuitConfig {
+            range_check,
+            compare,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.range_check.table.load(&mut layouter)?;
+
+        let range_check = &config.range_check;
+        let a = range_check.assign_lookup(layouter.namespace(|| "a"), self.a)?;
+        let b = range_check.assign_lookup(layouter.namespace(|| "b"), self.b)?;
+        let lo = range_check.assign_lookup(layouter.namespace(|| "lo"), self.lo)?;
+        let hi = range_check.assign_lookup(layouter.namespace(|| "hi"), self.hi)?;
+
+        let compare = &config.compare;
+        let outputs = [
+            compare.lt(layouter.namespace(|| "a < b"), &a, &b)?,
+            compare.le(layouter.namespace(|| "a <= b"), &a, &b)?,
+            compare.in_interval(layouter.namespace(|| "lo <= a < hi"), &a, &lo, &hi)?,
+        ];
+        for (row, out) in outputs.iter().enumerate() {
+            layouter.constrain_instance(out.cell(), config.instance, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn compare_circuit(
+    a: u64,
+    b: u64,
+    lo: u64,
+    hi: u64,
+) -> CompareCircuit<halo2_proofs::pasta::Fp, 256> {
+    use halo2_proofs::pasta::Fp;
+
+    let value = |v: u64| Value::known(Fp::from(v).into());
+    CompareCircuit::new(value(a), value(b), value(lo), value(hi))
+}
+
+#[test]
+fn e5_compare() {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let k = 9;
+    let bit = |b: bool| Fp::from(b as u64);
+
+    // 端の値と、lo = hi の空の区間
+    let values = [0, 1, 127, 128, 254, 255];
+    for a in values {
+        for b in values {
+            for (lo, hi) in [(0, 255), (127, 128), (128, 128), (2, 1)] {
+                let circuit = compare_circuit(a, b, lo, hi);
+                let expected = vec![bit(a < b), bit(a <= b), bit(lo <= a && a < hi)];
+
+                let prover = MockProver::run(k, &circuit, vec![expected.clone()]).unwrap();
+                assert_eq!(
+                    prover.verify(),
+                    Ok(()),
+                    "a = {}, b = {}, [{}, {})",
+                    a,
+                    b,
+                    lo,
+                    hi
+                );
+
+                // どの結果を反転させても落ちる
+                for row in 0..3 {
+                    let mut wrong = expected.clone();
+                    wrong[row] = Fp::one() - wrong[row];
+                    let prover = MockProver::run(k, &circuit, vec![wrong]).unwrap();
+                    assert!(prover.verify().is_err());
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn e5_compare_wraparound() {
+    use halo2_proofs::{
+        dev::{MockProver, VerifyFailure},
+        pasta::Fp,
+    };
+
+    let k = 9;
+    let (zero, one) = (Fp::zero(), Fp::one());
+
+    // -1 = p - 1 は体の上では 0 - 1 + 256 = 255 がテーブルに入るので、
+    // 範囲チェックがなければ -1 < 0 が通ってしまう。
+    let circuit = CompareCircuit::<Fp, 256>::new(
+        Value::known((-one).into()),
+        Value::known(zero.into()),
+        Value::known(zero.into()),
+        Value::known(Fp::from(255).into()),
+    );
+    let prover = MockProver::run(k, &circuit, vec![vec![one, one, zero]]).unwrap();
+    let failures = prover.verify().unwrap_err();
+    assert!(failures
+        .iter()
+        .any(|failure| matches!(failure, VerifyFailure::Lookup { .. })));
+
+    // 256は 256 - 0 = 256 がテーブルの外なので、256 < 0 を偽と言えない
+    let circuit = CompareCircuit::<Fp, 256>::new(
+        Value::known(Fp::from(256).into()),
+        Value::known(zero.into()),
+        Value::known(zero.into()),
+        Value::known(Fp::from(255).into()),
+    );
+    let prover = MockProver::run(k, &circuit, vec![vec![zero, zero, zero]]).unwrap();
+    let failures = prover.verify().unwrap_err();
+    assert!(failures
+        .iter()
+        .any(|failure| matches!(failure, VerifyFailure::Lookup { .. })));
+}
+
+#[test]
+fn e5_compare_soundness() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::pasta::Fp;
+
+    let circuit = compare_circuit(200, 255, 0, 201);
+    assert_sound(9, &circuit, vec![vec![Fp::one(), Fp::one(), Fp::one()]]);
+}
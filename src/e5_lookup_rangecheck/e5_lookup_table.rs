@@ -8,13 +8,13 @@ use halo2_proofs::{
 
 /// A lookup table of values from 0..RANGE.
 #[derive(Debug, Clone)]
-pub(super) struct RangeTableConfig<F: FieldExt, const RANGE: usize> {
-    pub(super) value: TableColumn,
+pub struct RangeTableConfig<F: FieldExt, const RANGE: usize> {
+    pub value: TableColumn,
     _marker: PhantomData<F>,
 }
 
 impl<F: FieldExt, const RANGE: usize> RangeTableConfig<F, RANGE> {
-    pub(super) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         let value = meta.lookup_table_column();
 
         Self {
@@ -23,14 +23,14 @@ impl<F: FieldExt, const RANGE: usize> RangeTableConfig<F, RANGE> {
         }
     }
 
-    pub(super) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         layouter.assign_table(
             || "load range-check table",
             |mut table| {
                 let mut offset = 0;
                 for value in 0..RANGE {
                     table.assign_cell(
-                        || "num_bits",
+                        || "value",
                         self.value,
                         offset,
                         || Value::known(F::from(value as u64)),
@@ -42,4 +42,58 @@ impl<F: FieldExt, const RANGE: usize> RangeTableConfig<F, RANGE> {
             },
         )
     }
-}
\ No newline at end of file
+}
+
+/// A lookup table of `(num_bits, value)` pairs with `value < 2^num_bits`,
+/// for every width `0..=K`.
+///
+/// One table serves range checks of any width up to `K` bits. Width 0 only
+/// holds `(0, 0)`, which is also what a disabled lookup row looks up.
+#[derive(Debug, Clone)]
+pub struct TaggedRangeTableConfig<F: FieldExt, const K: usize> {
+    pub num_bits: TableColumn,
+    pub value: TableColumn,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const K: usize> TaggedRangeTableConfig<F, K> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let num_bits = meta.lookup_table_column();
+        let value = meta.lookup_table_column();
+
+        Self {
+            num_bits,
+            value,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load tagged range-check table",
+            |mut table| {
+                // (0, 0), (1, 0), (1, 1), (2, 0), ..., (K, 2^K - 1)
+                let mut offset = 0;
+                for num_bits in 0..=K {
+                    for value in 0..(1u64 << num_bits) {
+                        table.assign_cell(
+                            || "num_bits",
+                            self.num_bits,
+                            offset,
+                            || Value::known(F::from(num_bits as u64)),
+                        )?;
+                        table.assign_cell(
+                            || "value",
+                            self.value,
+                            offset,
+                            || Value::known(F::from(value)),
+                        )?;
+                        offset += 1;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
@@ -0,0 +1,450 @@
+use std::{
+    io::{self, Read},
+    iter,
+    marker::PhantomData,
+};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, Layouter, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Selector, TableColumn,
+        VirtualCells,
+    },
+    poly::Rotation,
+};
+
+use crate::soundness::min_k;
+
+// RangeTableConfigは0..RANGEをループで作るだけなので、任意の表 (国コードの一覧、S-boxなど) を
+// 引きたいときは毎回chipを書くことになる。ここでは表をファイルやiteratorから読んで、
+// N列のTableColumnに入れる。
+//
+// lookupの入力はselectorが0の行では (0, 0, ..., 0) になるので、表にその行がないと落ちる。
+// 表の中身に0があるとは限らないので、tag列を足して先頭に (0, 0, ..., 0) を置く。
+//
+//   tag | column_0 | column_1
+//  -----+----------+----------
+//    0  |    0     |    0       <- selectorが0の行が引く行
+//    1  |   x_0    |   y_0
+//    1  |   x_1    |   y_1
+//    0  |    0     |    0       <- 残りはhalo2が0行目で埋める
+//
+// 入力は (q, q * x, q * y) になる。
+
+/// The rows of an `N`-column lookup table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table<F: FieldExt, const N: usize> {
+    rows: Vec<[F; N]>,
+}
+
+impl<F: FieldExt, const N: usize> Table<F, N> {
+    pub fn new(rows: impl IntoIterator<Item = [F; N]>) -> Self {
+        Self {
+            rows: rows.into_iter().collect(),
+        }
+    }
+
+    /// Reads one row per line, `N` comma-separated values.
+    ///
+    /// Values are decimal or `0x` hexadecimal, optionally negative. Empty
+    /// lines and lines starting with `#` are skipped.
+    pub fn from_csv(mut reader: impl Read) -> io::Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| row(line.split(',').map(str::trim)))
+            .collect::<io::Result<_>>()
+            .map(|rows| Self { rows })
+    }
+
+    /// Reads a JSON array of rows, e.g. `[[1, 2], [3, "0x04"]]`.
+    ///
+    /// A row is an array of `N` numbers or strings, formatted as in
+    /// [`Table::from_csv`]. When `N` is 1 a row can also be a bare value.
+    pub fn from_json(reader: impl Read) -> io::Result<Self> {
+        let rows: Vec<serde_json::Value> = serde_json::from_reader(reader)?;
+        rows.into_iter()
+            .map(|values| {
+                let values = match values {
+                    serde_json::Value::Array(values) => values,
+                    value => vec![value],
+                };
+                let values = values
+                    .into_iter()
+                    .map(|value| match value {
+                        serde_json::Value::Number(n) => Ok(n.to_string()),
+                        serde_json::Value::String(s) => Ok(s),
+                        value => Err(invalid(&format!("not a number: {}", value))),
+                    })
+                    .collect::<io::Result<Vec<_>>>()?;
+                row(values.iter().map(String::as_str))
+            })
+            .collect::<io::Result<_>>()
+            .map(|rows| Self { rows })
+    }
+
+    /// Reads rows of `N` field elements, each in the little-endian byte
+    /// encoding of `F::to_repr`.
+    pub fn from_binary(mut reader: impl Read) -> io::Result<Self> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        let size = F::Repr::default().as_ref().len();
+        if N == 0 || data.len() % (size * N) != 0 {
+            return Err(invalid("the file is not a whole number of rows"));
+        }
+
+        data.chunks(size * N)
+            .map(|bytes| {
+                let mut row = [F::zero(); N];
+                for (value, bytes) in row.iter_mut().zip(bytes.chunks(size)) {
+                    let mut repr = F::Repr::default();
+                    repr.as_mut().copy_from_slice(bytes);
+                    *value = Option::from(F::from_repr(repr))
+                        .ok_or_else(|| invalid("not a canonical field element"))?;
+                }
+                Ok(row)
+            })
+            .collect::<io::Result<_>>()
+            .map(|rows| Self { rows })
+    }
+
+    pub fn rows(&self) -> &[[F; N]] {
+        &self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn contains(&self, row: &[F; N]) -> bool {
+        self.rows.contains(row)
+    }
+}
+
+fn row<'a, F: FieldExt, const N: usize>(
+    values: impl Iterator<Item = &'a str>,
+) -> io::Result<[F; N]> {
+    values
+        .map(parse_field)
+        .collect::<io::Result<Vec<_>>>()?
+        .try_into()
+        .map_err(|_| invalid(&format!("expected {} values in a row", N)))
+}
+
+fn parse_field<F: FieldExt>(value: &str) -> io::Result<F> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let (radix, digits) = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(digits) => (16, digits),
+        None => (10, value),
+    };
+    if digits.is_empty() {
+        return Err(invalid(&format!("not a number: {:?}", value)));
+    }
+
+    let value = digits
+        .chars()
+        .try_fold(F::zero(), |acc, c| {
+            c.to_digit(radix)
+                .map(|d| acc * F::from(radix as u64) + F::from(d as u64))
+        })
+        .ok_or_else(|| invalid(&format!("not a number: {:?}", value)))?;
+    Ok(if negative { -value } else { value })
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// `N` table columns plus a tag column, loaded from a [`Table`].
+#[derive(Debug, Clone)]
+pub struct TableConfig<F: FieldExt, const N: usize> {
+    tag: TableColumn,
+    pub columns: [TableColumn; N],
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const N: usize> TableConfig<F, N> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            tag: meta.lookup_table_column(),
+            columns: [(); N].map(|_| meta.lookup_table_column()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Looks up `inputs` on the rows where `selector` is enabled.
+    ///
+    /// `selector` must be a complex selector.
+    pub fn lookup(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        selector: Selector,
+        inputs: impl FnOnce(&mut VirtualCells<'_, F>) -> [Expression<F>; N],
+    ) {
+        meta.lookup(|meta| {
+            let q = meta.query_selector(selector);
+            let inputs = inputs(meta);
+
+            iter::once((q.clone(), self.tag))
+                .chain(
+                    inputs
+                        .into_iter()
+                        .zip(self.columns)
+                        .map(|(input, column)| (q.clone() * input, column)),
+                )
+                .collect()
+        });
+    }
+
+    /// Loads `table` after a row of zeros, so it takes `table.len() + 1` rows.
+    ///
+    /// A table that does not fit fails with `Error::NotEnoughRowsAvailable`.
+    pub fn load(&self, layouter: &mut impl Layouter<F>, table: &Table<F, N>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load table",
+            |mut t| {
+                t.assign_cell(|| "tag", self.tag, 0, || Value::known(F::zero()))?;
+                for column in self.columns {
+                    t.assign_cell(|| "padding", column, 0, || Value::known(F::zero()))?;
+                }
+
+                for (i, row) in table.rows().iter().enumerate() {
+                    let offset = i + 1;
+                    t.assign_cell(|| "tag", self.tag, offset, || Value::known(F::one()))?;
+                    for (column, value) in self.columns.iter().zip(row) {
+                        t.assign_cell(|| "value", *column, offset, || Value::known(*value))?;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TableCircuitConfig<F: FieldExt, const N: usize> {
+    advice: [Column<Advice>; N],
+    q_lookup: Selector,
+    pub table: TableConfig<F, N>,
+}
+
+/// Checks that every witnessed row is a row of `table`.
+pub struct TableCircuit<F: FieldExt, const N: usize> {
+    table: Table<F, N>,
+    rows: Vec<[Value<F>; N]>,
+}
+
+impl<F: FieldExt, const N: usize> TableCircuit<F, N> {
+    pub fn new(table: Table<F, N>, rows: Vec<[Value<F>; N]>) -> Self {
+        Self { table, rows }
+    }
+
+    /// The smallest `k` that fits both the table and the witnessed rows.
+    pub fn min_k(table: &Table<F, N>, rows: usize) -> u32 {
+        let mut meta = ConstraintSystem::<F>::default();
+        Self::configure(&mut meta);
+        min_k((table.len() + 1).max(rows), &meta)
+    }
+}
+
+impl<F: FieldExt, const N: usize> Circuit<F> for TableCircuit<F, N> {
+    type Config = TableCircuitConfig<F, N>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(
+            self.table.clone(),
+            vec![[Value::unknown(); N]; self.rows.len()],
+        )
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); N].map(|_| meta.advice_column());
+        let q_lookup = meta.complex_selector();
+        let table = TableConfig::configure(meta);
+
+        table.lookup(meta, q_lookup, |meta| {
+            advice.map(|column| meta.query_advice(column, Rotation::cur()))
+        });
+
+        TableCircuitConfig {
+            advice,
+            q_lookup,
+            table,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.table.load(&mut layouter, &self.table)?;
+
+        layouter.assign_region(
+            || "rows",
+            |mut region| {
+                for (offset, row) in self.rows.iter().enumerate() {
+                    config.q_lookup.enable(&mut region, offset)?;
+                    for (column, value) in config.advice.iter().zip(row) {
+                        region.assign_advice(|| "value", *column, offset, || *value)?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn e5_table_loader_csv() {
+    use crate::soundness::mock_verify;
+    use halo2_proofs::pasta::Fp;
+
+    // ISO 3166-1の数字コード。0は入っていない
+    let csv = "# allowed countries\n392\n840\n 276 \n\n0x50\n";
+    let table = Table::<Fp, 1>::from_csv(csv.as_bytes()).unwrap();
+    assert_eq!(table.len(), 4);
+    assert!(table.contains(&[Fp::from(80)]));
+
+    let check = |codes: &[u64]| {
+        let rows = codes
+            .iter()
+            .map(|code| [Value::known(Fp::from(*code))])
+            .collect::<Vec<_>>();
+        let k = TableCircuit::min_k(&table, rows.len());
+        let circuit = TableCircuit::new(table.clone(), rows);
+        mock_verify(k, &circuit, vec![])
+    };
+
+    assert_eq!(check(&[392, 276, 392, 80]), Ok(()));
+    assert!(check(&[392, 124]).is_err());
+    // selectorが0の行のための0行目は、tagが違うので引けない
+    assert!(check(&[0]).is_err());
+}
+
+#[test]
+fn e5_table_loader_json() {
+    use crate::soundness::mock_verify;
+    use halo2_proofs::pasta::Fp;
+
+    // PRESENTの4ビットS-box
+    let sbox = [
+        0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2,
+    ];
+    let json = format!(
+        "[{}]",
+        sbox.iter()
+            .enumerate()
+            .map(|(x, y)| format!("[{}, \"0x{:x}\"]", x, y))
+            .collect::<Vec<_>>()
+            .join(",\n ")
+    );
+    let table = Table::<Fp, 2>::from_json(json.as_bytes()).unwrap();
+    assert_eq!(
+        table,
+        Table::new(
+            sbox.iter()
+                .enumerate()
+                .map(|(x, y)| [Fp::from(x as u64), Fp::from(*y)])
+        )
+    );
+
+    let check = |pairs: &[(u64, u64)]| {
+        let rows = pairs
+            .iter()
+            .map(|(x, y)| [Value::known(Fp::from(*x)), Value::known(Fp::from(*y))])
+            .collect::<Vec<_>>();
+        let k = TableCircuit::min_k(&table, rows.len());
+        let circuit = TableCircuit::new(table.clone(), rows);
+        mock_verify(k, &circuit, vec![])
+    };
+
+    assert_eq!(check(&[(0, 0xc), (5, 0x0), (15, 0x2)]), Ok(()));
+    // 列ごとには表にある値でも、組として表にない
+    assert!(check(&[(0, 0x5)]).is_err());
+
+    // 1列なら値をそのまま並べてもいい
+    let table = Table::<Fp, 1>::from_json(&b" [1, \"-2\", [3]] "[..]).unwrap();
+    assert_eq!(
+        table.rows(),
+        &[[Fp::from(1)], [-Fp::from(2)], [Fp::from(3)]]
+    );
+    assert!(Table::<Fp, 1>::from_json(&b"[]"[..]).unwrap().is_empty());
+}
+
+#[test]
+fn e5_table_loader_binary() {
+    use halo2_proofs::pasta::Fp;
+
+    fn bytes<F: FieldExt, const N: usize>(table: &Table<F, N>) -> Vec<u8> {
+        table
+            .rows()
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_repr().as_ref().to_vec())
+            .collect()
+    }
+
+    let table = Table::<Fp, 2>::new((0..10u64).map(|i| [Fp::from(i), -Fp::from(i * i)]));
+    let bytes = bytes(&table);
+    assert_eq!(Table::from_binary(&bytes[..]).unwrap(), table);
+
+    // 半端な行、pより大きい値
+    assert!(Table::<Fp, 2>::from_binary(&bytes[..bytes.len() - 1]).is_err());
+    assert!(Table::<Fp, 1>::from_binary(&[0xff; 32][..]).is_err());
+}
+
+#[test]
+fn e5_table_loader_errors() {
+    use halo2_proofs::pasta::Fp;
+
+    assert!(Table::<Fp, 2>::from_csv(&b"1,2\n3\n"[..]).is_err());
+    assert!(Table::<Fp, 1>::from_csv(&b"1,2\n"[..]).is_err());
+    assert!(Table::<Fp, 1>::from_csv(&b"12a\n"[..]).is_err());
+    assert!(Table::<Fp, 1>::from_csv(&b"0x\n"[..]).is_err());
+
+    assert!(Table::<Fp, 2>::from_json(&b"[[1, 2], [3]]"[..]).is_err());
+    assert!(Table::<Fp, 1>::from_json(&b"[1, 2"[..]).is_err());
+    assert!(Table::<Fp, 1>::from_json(&b"[1] 2"[..]).is_err());
+    assert!(Table::<Fp, 1>::from_json(&b"[\"1\\n\"]"[..]).is_err());
+}
+
+#[test]
+fn e5_table_loader_size() {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let k = 6;
+    let mut meta = ConstraintSystem::<Fp>::default();
+    TableCircuit::<Fp, 1>::configure(&mut meta);
+    let usable_rows = (1 << k) - meta.blinding_factors() - 1;
+
+    // tag = 0の行があるので、使えるのはusable_rows - 1行まで
+    let run = |len: usize| {
+        let table = Table::new((0..len as u64).map(|i| [Fp::from(i)]));
+        let circuit = TableCircuit::new(table, vec![[Value::known(Fp::from(1))]]);
+        MockProver::run(k, &circuit, vec![])
+    };
+
+    assert_eq!(run(usable_rows - 1).unwrap().verify(), Ok(()));
+    assert!(matches!(
+        run(usable_rows),
+        Err(Error::NotEnoughRowsAvailable { current_k }) if current_k == k
+    ));
+}
@@ -0,0 +1,199 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Fixed, Selector},
+    poly::Rotation,
+};
+
+use super::TaggedRangeTableConfig;
+
+// RangeCheckConfigのlookupは0..LOOKUP_RANGEしか調べられないので、
+// 幅の違うチェックにはテーブルを分けるか、値をずらして2回引く必要があった。
+// ここではテーブルに幅のタグを付けて、(num_bits, value) の組で引く。
+//
+//   value | num_bits | q_lookup
+//  -------+----------+----------
+//    v_0  |    3     |    1
+//    v_1  |    8     |    1
+//
+// num_bitsはfixed columnなので、proverは幅を変えられない。
+
+/// A value checked to be in `0..2^num_bits` against the tagged table.
+#[derive(Debug, Clone)]
+pub struct TaggedRangeConstrained<F: FieldExt> {
+    cell: AssignedCell<Assigned<F>, F>,
+    num_bits: usize,
+}
+
+impl<F: FieldExt> TaggedRangeConstrained<F> {
+    pub fn cell(&self) -> &AssignedCell<Assigned<F>, F> {
+        &self.cell
+    }
+
+    /// The value is known to be less than `2^num_bits`.
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaggedRangeCheckConfig<F: FieldExt, const K: usize> {
+    q_lookup: Selector,
+    value: Column<Advice>,
+    num_bits: Column<Fixed>,
+    pub table: TaggedRangeTableConfig<F, K>,
+}
+
+impl<F: FieldExt, const K: usize> TaggedRangeCheckConfig<F, K> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
+        let q_lookup = meta.complex_selector();
+        let num_bits = meta.fixed_column();
+        let table = TaggedRangeTableConfig::configure(meta);
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let num_bits = meta.query_fixed(num_bits, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+
+            // selectorが0の行は (0, 0) を引く
+            vec![
+                (q.clone() * num_bits, table.num_bits),
+                (q * value, table.value),
+            ]
+        });
+
+        Self {
+            q_lookup,
+            value,
+            num_bits,
+            table,
+        }
+    }
+
+    /// Witnesses `value` and checks that it is in `0..2^num_bits`.
+    ///
+    /// Fails with `Error::Synthesis` if `num_bits > K`, since the table only
+    /// covers widths up to `K`.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<Assigned<F>>,
+        num_bits: usize,
+    ) -> Result<TaggedRangeConstrained<F>, Error> {
+        if num_bits > K {
+            return Err(Error::Synthesis);
+        }
+
+        layouter.assign_region(
+            || "tagged range check",
+            |mut region| {
+                let offset = 0;
+
+                self.q_lookup.enable(&mut region, offset)?;
+                region.assign_fixed(
+                    || "num_bits",
+                    self.num_bits,
+                    offset,
+                    || Value::known(F::from(num_bits as u64)),
+                )?;
+
+                let cell = region.assign_advice(|| "value", self.value, offset, || value)?;
+                Ok(TaggedRangeConstrained { cell, num_bits })
+            },
+        )
+    }
+}
+
+/// Checks each value against its own width with the one tagged table.
+pub struct TaggedCircuit<F: FieldExt, const K: usize> {
+    values: Vec<(Value<Assigned<F>>, usize)>,
+}
+
+impl<F: FieldExt, const K: usize> TaggedCircuit<F, K> {
+    /// Each value is checked to be in `0..2^num_bits`, `num_bits <= K`.
+    pub fn new(values: Vec<(Value<Assigned<F>>, usize)>) -> Self {
+        Self { values }
+    }
+}
+
+impl<F: FieldExt, const K: usize> Circuit<F> for TaggedCircuit<F, K> {
+    type Config = TaggedRangeCheckConfig<F, K>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(
+            self.values
+                .iter()
+                .map(|(_, num_bits)| (Value::unknown(), *num_bits))
+                .collect(),
+        )
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        TaggedRangeCheckConfig::configure(meta, value)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.table.load(&mut layouter)?;
+
+        for (value, num_bits) in &self.values {
+            config.assign(layouter.namespace(|| "value"), *value, *num_bits)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn e5_tagged_rangecheck() {
+    use crate::soundness::mock_verify;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // テーブルは 2^0 + 2^1 + ... + 2^8 = 511行
+    const K: usize = 8;
+    let k = 10;
+    let check = |values: &[(u64, usize)]| {
+        let values = values
+            .iter()
+            .map(|(v, num_bits)| (Value::known(Fp::from(*v).into()), *num_bits))
+            .collect();
+        let circuit = TaggedCircuit::<Fp, K>::new(values);
+        mock_verify(k, &circuit, vec![])
+    };
+
+    // 全部の幅を1つのテーブルで
+    let honest = (0..=K).map(|n| ((1 << n) - 1, n)).collect::<Vec<_>>();
+    assert_eq!(check(&honest), Ok(()));
+
+    for n in 0..=K {
+        assert_eq!(check(&[(0, n)]), Ok(()), "0 < 2^{}", n);
+        assert!(check(&[(1 << n, n)]).is_err(), "2^{} accepted", n);
+    }
+    // 広い幅のタグでは通る値も、狭いタグでは落ちる
+    assert_eq!(check(&[(5, 3)]), Ok(()));
+    assert!(check(&[(5, 2)]).is_err());
+
+    // テーブルにないタグは回路が作れない
+    let circuit = TaggedCircuit::<Fp, K>::new(vec![(Value::known(Fp::zero().into()), K + 1)]);
+    assert!(matches!(
+        MockProver::run(k, &circuit, vec![]),
+        Err(Error::Synthesis)
+    ));
+}
+
+#[test]
+fn e5_tagged_rangecheck_soundness() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::pasta::Fp;
+
+    let circuit = TaggedCircuit::<Fp, 8>::new(vec![
+        (Value::known(Fp::from(7).into()), 3),
+        (Value::known(Fp::from(255).into()), 8),
+    ]);
+    assert_sound(10, &circuit, vec![]);
+}
@@ -10,13 +10,26 @@ use poseidon_circuit::poseidon::{
     Hash, Pow5Chip, Pow5Config,
 };
 
-struct HashCircuit<S: Spec<Fr, WIDTH, RATE>, const WIDTH: usize, const RATE: usize, const L: usize>
+pub struct HashCircuit<S: Spec<Fr, WIDTH, RATE>, const WIDTH: usize, const RATE: usize, const L: usize>
 {
     message: Option<[Fr; L]>,
     output: Option<Fr>,
     _spec: PhantomData<S>,
 }
 
+impl<S: Spec<Fr, WIDTH, RATE>, const WIDTH: usize, const RATE: usize, const L: usize>
+    HashCircuit<S, WIDTH, RATE, L>
+{
+    /// Proves that `output` is the Poseidon hash of `message`.
+    pub fn new(message: Option<[Fr; L]>, output: Option<Fr>) -> Self {
+        Self {
+            message,
+            output,
+            _spec: PhantomData,
+        }
+    }
+}
+
 impl<S: Spec<Fr, WIDTH, RATE>, const WIDTH: usize, const RATE: usize, const L: usize> Circuit<Fr>
     for HashCircuit<S, WIDTH, RATE, L>
 {
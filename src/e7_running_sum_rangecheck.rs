@@ -0,0 +1,326 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Region, Value},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Selector,
+    },
+    poly::Rotation,
+};
+
+use crate::e5_lookup_rangecheck::RangeTableConfig;
+
+// e5のlookupはテーブルの大きさ (LOOKUP_RANGE = 2^K) までしか調べられないので、
+// valueをKビットずつのlimbに分けて、limbごとにテーブルを引く。
+//
+//   z_0 = value
+//   z_{i+1} = (z_i - a_i) / 2^K        (a_i = z_i - 2^K * z_{i+1} がi番目のlimb)
+//
+// num_bits = K * n + r のとき、n個のlimbをlookupしたあとに残るz_nはr < Kビットのはず。
+// z_n < 2^r は「z_nとz_n * 2^(K - r)の両方がテーブルにある」で調べる。
+// r = 0 なら z_n * 2^K < 2^K なので z_n = 0 になる。
+//
+//   z     | q_running | q_short | q_bitshift | shift
+//  -------+-----------+---------+------------+-----------
+//   z_0   |     1     |         |            |
+//   z_1   |     1     |         |            |
+//   ..    |           |         |            |
+//   z_n   |           |    1    |     1      | 2^(K - r)
+//   z_n'  |           |    1    |            |
+//
+// lookupの中身は q_running * (z_cur - 2^K * z_next) + q_short * z_cur。
+
+/// A value that was decomposed into `K`-bit limbs and checked to be in
+/// `0..2^num_bits`.
+#[derive(Debug, Clone)]
+pub struct RunningSum<F: FieldExt> {
+    value: AssignedCell<Assigned<F>, F>,
+    zs: Vec<AssignedCell<Assigned<F>, F>>,
+    num_bits: usize,
+}
+
+impl<F: FieldExt> RunningSum<F> {
+    /// The cell holding the checked value, i.e. `z_0`.
+    pub fn cell(&self) -> &AssignedCell<Assigned<F>, F> {
+        &self.value
+    }
+
+    /// `z_0, z_1, ..., z_n` of the running sum.
+    pub fn zs(&self) -> &[AssignedCell<Assigned<F>, F>] {
+        &self.zs
+    }
+
+    /// The value is known to be less than `2^num_bits`.
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RunningSumConfig<F: FieldExt, const LOOKUP_RANGE: usize> {
+    z: Column<Advice>,
+    shift: Column<Fixed>,
+    q_running: Selector,
+    q_short: Selector,
+    q_bitshift: Selector,
+    pub table: RangeTableConfig<F, LOOKUP_RANGE>,
+}
+
+impl<F: FieldExt, const LOOKUP_RANGE: usize> RunningSumConfig<F, LOOKUP_RANGE> {
+    /// Bits per limb, `LOOKUP_RANGE = 2^K`.
+    pub const K: usize = LOOKUP_RANGE.trailing_zeros() as usize;
+
+    /// `table` can be shared with other chips, e.g. the one of
+    /// `e5_lookup_rangecheck::RangeCheckConfig`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        z: Column<Advice>,
+        table: RangeTableConfig<F, LOOKUP_RANGE>,
+    ) -> Self {
+        assert!(
+            LOOKUP_RANGE.is_power_of_two() && LOOKUP_RANGE > 1,
+            "the table must hold all K-bit values"
+        );
+
+        let shift = meta.fixed_column();
+        let q_running = meta.complex_selector();
+        let q_short = meta.complex_selector();
+        let q_bitshift = meta.selector();
+
+        meta.enable_equality(z);
+
+        meta.lookup(|meta| {
+            let q_running = meta.query_selector(q_running);
+            let q_short = meta.query_selector(q_short);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+
+            let two_pow_k = Expression::Constant(F::from(LOOKUP_RANGE as u64));
+            let limb = z_cur.clone() - z_next * two_pow_k;
+
+            vec![(q_running * limb + q_short * z_cur, table.value)]
+        });
+
+        meta.create_gate("bitshift", |meta| {
+            let q = meta.query_selector(q_bitshift);
+            let shift = meta.query_fixed(shift, Rotation::cur());
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+
+            vec![q * (z_next - z_cur * shift)]
+        });
+
+        Self {
+            z,
+            shift,
+            q_running,
+            q_short,
+            q_bitshift,
+            table,
+        }
+    }
+
+    /// Witnesses `value` and checks that it is in `0..2^num_bits`.
+    ///
+    /// Fails with `Error::Synthesis` unless `num_bits` is below the field size.
+    pub fn witness_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<Assigned<F>>,
+        num_bits: usize,
+    ) -> Result<RunningSum<F>, Error> {
+        layouter.assign_region(
+            || "running sum range check",
+            |mut region| {
+                let z_0 = region.assign_advice(|| "z_0", self.z, 0, || value)?;
+                self.decompose(&mut region, z_0, num_bits)
+            },
+        )
+    }
+
+    /// Checks that the value of an existing cell is in `0..2^num_bits`.
+    ///
+    /// Fails with `Error::Synthesis` unless `num_bits` is below the field size.
+    pub fn copy_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<Assigned<F>, F>,
+        num_bits: usize,
+    ) -> Result<RunningSum<F>, Error> {
+        layouter.assign_region(
+            || "running sum range check",
+            |mut region| {
+                let z_0 = value.copy_advice(|| "z_0", &mut region, self.z, 0)?;
+                self.decompose(&mut region, z_0, num_bits)
+            },
+        )
+    }
+
+    fn decompose(
+        &self,
+        region: &mut Region<'_, F>,
+        z_0: AssignedCell<Assigned<F>, F>,
+        num_bits: usize,
+    ) -> Result<RunningSum<F>, Error> {
+        // limbの和がpを超えて回り込まないように
+        if num_bits >= F::NUM_BITS as usize {
+            return Err(Error::Synthesis);
+        }
+        let k = Self::K;
+        let (full_limbs, short_bits) = (num_bits / k, num_bits % k);
+
+        let two_pow_k_inv = Assigned::from(F::from(LOOKUP_RANGE as u64)).invert();
+        let limbs = z_0.value().map(|v| limbs(v.evaluate(), k, full_limbs));
+
+        let mut zs = vec![z_0.clone()];
+        for i in 0..full_limbs {
+            self.q_running.enable(region, i)?;
+
+            let limb = limbs.as_ref().map(|limbs| Assigned::from(limbs[i]));
+            let z_next = (zs[i].value().copied() - limb) * Value::known(two_pow_k_inv);
+            let cell = region.assign_advice(|| format!("z_{}", i + 1), self.z, i + 1, || z_next)?;
+            zs.push(cell);
+        }
+
+        // 残りのz_nがshort_bitsビットに収まっているか
+        let row = full_limbs;
+        self.q_short.enable(region, row)?;
+        self.q_short.enable(region, row + 1)?;
+        self.q_bitshift.enable(region, row)?;
+
+        let shift = F::from(1u64 << (k - short_bits));
+        region.assign_fixed(|| "2^(K - r)", self.shift, row, || Value::known(shift))?;
+        region.assign_advice(
+            || "z_n * 2^(K - r)",
+            self.z,
+            row + 1,
+            || zs[row].value().copied() * Value::known(Assigned::from(shift)),
+        )?;
+
+        Ok(RunningSum {
+            value: z_0,
+            zs,
+            num_bits,
+        })
+    }
+}
+
+/// The lowest `count` `k`-bit limbs of `value`, little-endian.
+fn limbs<F: FieldExt>(value: F, k: usize, count: usize) -> Vec<F> {
+    let repr = value.to_repr();
+    let bit = |i: usize| {
+        repr.as_ref()
+            .get(i / 8)
+            .map_or(false, |byte| (byte >> (i % 8)) & 1 == 1)
+    };
+
+    (0..count)
+        .map(|limb| {
+            (0..k).rev().fold(F::zero(), |acc, j| {
+                acc.double() + F::from(bit(limb * k + j) as u64)
+            })
+        })
+        .collect()
+}
+
+/// Checks each value against its own bit width with one shared table.
+pub struct MyCircuit<F: FieldExt, const LOOKUP_RANGE: usize> {
+    values: Vec<(Value<Assigned<F>>, usize)>,
+}
+
+impl<F: FieldExt, const LOOKUP_RANGE: usize> MyCircuit<F, LOOKUP_RANGE> {
+    /// Each value is checked to be in `0..2^num_bits`.
+    pub fn new(values: Vec<(Value<Assigned<F>>, usize)>) -> Self {
+        Self { values }
+    }
+}
+
+impl<F: FieldExt, const LOOKUP_RANGE: usize> Circuit<F> for MyCircuit<F, LOOKUP_RANGE> {
+    type Config = RunningSumConfig<F, LOOKUP_RANGE>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(
+            self.values
+                .iter()
+                .map(|(_, num_bits)| (Value::unknown(), *num_bits))
+                .collect(),
+        )
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let z = meta.advice_column();
+        let table = RangeTableConfig::configure(meta);
+        RunningSumConfig::configure(meta, z, table)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.table.load(&mut layouter)?;
+
+        for (value, num_bits) in &self.values {
+            config.witness_check(layouter.namespace(|| "value"), *value, *num_bits)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn e7_running_sum_rangecheck() {
+    use crate::soundness::mock_verify;
+    use halo2_proofs::{arithmetic::Field, dev::MockProver, pasta::Fp};
+
+    // K = 8
+    const LOOKUP_RANGE: usize = 256;
+    let k = 9;
+    let two_pow = |bits: u64| Fp::from(2).pow_vartime(&[bits]);
+    let check = |value: Fp, num_bits: usize| {
+        let circuit =
+            MyCircuit::<Fp, LOOKUP_RANGE>::new(vec![(Value::known(value.into()), num_bits)]);
+        mock_verify(k, &circuit, vec![])
+    };
+
+    // (bit幅, その幅の最大値が通って、次が落ちる)
+    // 16 = 8 * 2 は短いlimbなし、10, 64 + 5, 253 = 8 * 31 + 5 は短いlimbあり
+    for num_bits in [1, 8, 10, 16, 64, 69, 253] {
+        let bound = two_pow(num_bits as u64);
+        assert_eq!(check(Fp::zero(), num_bits), Ok(()), "0 < 2^{}", num_bits);
+        assert_eq!(
+            check(bound - Fp::one(), num_bits),
+            Ok(()),
+            "2^{} - 1",
+            num_bits
+        );
+        assert!(check(bound, num_bits).is_err(), "2^{} accepted", num_bits);
+    }
+
+    // 64ビットの値
+    assert_eq!(check(Fp::from(u64::MAX), 64), Ok(()));
+    assert!(check(Fp::from(u64::MAX), 63).is_err());
+    // pに近い値 (= 負の数) は253ビットに収まらない
+    assert!(check(-Fp::one(), 253).is_err());
+
+    // 体のサイズ以上のbit幅は回路が作れない
+    let circuit = MyCircuit::<Fp, LOOKUP_RANGE>::new(vec![(Value::known(Fp::one().into()), 255)]);
+    assert!(matches!(
+        MockProver::run(k, &circuit, vec![]),
+        Err(Error::Synthesis)
+    ));
+}
+
+#[test]
+fn e7_running_sum_rangecheck_soundness() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::pasta::Fp;
+
+    // 8ビットのlimbを2つと、3ビットの短いlimb
+    // 全部のビットを1にしておくと、z_0を1増やしたときもlimbがテーブルからはみ出す
+    let circuit = MyCircuit::<Fp, 256>::new(vec![
+        (Value::known(Fp::from(0x7ffffu64).into()), 19),
+        (Value::known(Fp::from(0xffffu64).into()), 16),
+    ]);
+    assert_sound(9, &circuit, vec![]);
+}
@@ -0,0 +1,258 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Fixed, Selector},
+    poly::Rotation,
+};
+
+use crate::e5_lookup_rangecheck::RangeTableConfig;
+use crate::e7_running_sum_rangecheck::RunningSumConfig;
+
+// e4, e5のRANGEはconst genericなので、範囲ごとに別のconfigが要る。
+// ここでは範囲を呼び出しごとに決めて、gateとtableは1つを使い回す。
+//
+// 0 <= v < bound は、2^(n-1) < bound <= 2^n となるnについて
+//
+//   v < 2^n  かつ  v + (2^n - bound) < 2^n
+//
+// と同じ。どちらもe7のrunning sumでnビットのチェックをする。
+// boundが2のべきなら1つ目だけでいい。
+//
+//   value | q_offset | offset
+//  -------+----------+-------------
+//    v    |    1     | 2^n - bound
+//    w    |          |
+
+/// A cell whose value was checked to be in `0..bound` by
+/// [`DynamicRangeConfig`]. It can only be created by the chip, so holding one
+/// is the guarantee.
+#[derive(Debug, Clone)]
+pub struct Bounded<F: FieldExt> {
+    cell: AssignedCell<Assigned<F>, F>,
+    bound: u64,
+}
+
+impl<F: FieldExt> Bounded<F> {
+    pub fn cell(&self) -> &AssignedCell<Assigned<F>, F> {
+        &self.cell
+    }
+
+    /// The value is known to be less than `bound`.
+    pub fn bound(&self) -> u64 {
+        self.bound
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamicRangeConfig<F: FieldExt, const LOOKUP_RANGE: usize> {
+    value: Column<Advice>,
+    offset: Column<Fixed>,
+    q_offset: Selector,
+    pub running_sum: RunningSumConfig<F, LOOKUP_RANGE>,
+}
+
+impl<F: FieldExt, const LOOKUP_RANGE: usize> DynamicRangeConfig<F, LOOKUP_RANGE> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        value: Column<Advice>,
+        table: RangeTableConfig<F, LOOKUP_RANGE>,
+    ) -> Self {
+        let offset = meta.fixed_column();
+        let q_offset = meta.selector();
+        let running_sum = RunningSumConfig::configure(meta, value, table);
+
+        meta.create_gate("offset", |meta| {
+            let q = meta.query_selector(q_offset);
+            let v = meta.query_advice(value, Rotation::cur());
+            let w = meta.query_advice(value, Rotation::next());
+            let offset = meta.query_fixed(offset, Rotation::cur());
+
+            vec![q * (w - v - offset)]
+        });
+
+        Self {
+            value,
+            offset,
+            q_offset,
+            running_sum,
+        }
+    }
+
+    /// Witnesses `value` and checks that it is in `0..bound`.
+    ///
+    /// Fails with `Error::Synthesis` if `bound` is 0.
+    pub fn witness_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<Assigned<F>>,
+        bound: u64,
+    ) -> Result<Bounded<F>, Error> {
+        let num_bits = Self::num_bits(bound)?;
+        let v =
+            self.running_sum
+                .witness_check(layouter.namespace(|| "v < 2^n"), value, num_bits)?;
+        self.check_upper(layouter, v.cell().clone(), bound)
+    }
+
+    /// Checks that the value of an existing cell is in `0..bound`.
+    ///
+    /// Fails with `Error::Synthesis` if `bound` is 0.
+    pub fn copy_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<Assigned<F>, F>,
+        bound: u64,
+    ) -> Result<Bounded<F>, Error> {
+        let num_bits = Self::num_bits(bound)?;
+        let v = self
+            .running_sum
+            .copy_check(layouter.namespace(|| "v < 2^n"), value, num_bits)?;
+        self.check_upper(layouter, v.cell().clone(), bound)
+    }
+
+    /// The smallest `n` with `bound <= 2^n`.
+    fn num_bits(bound: u64) -> Result<usize, Error> {
+        // 0未満の値はないので、bound = 0は示せない
+        if bound == 0 {
+            return Err(Error::Synthesis);
+        }
+        Ok(64 - (bound - 1).leading_zeros() as usize)
+    }
+
+    // v < 2^n はもう調べてあるので、v + 2^n - bound < 2^n を足す
+    fn check_upper(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: AssignedCell<Assigned<F>, F>,
+        bound: u64,
+    ) -> Result<Bounded<F>, Error> {
+        let num_bits = Self::num_bits(bound)?;
+        if bound.is_power_of_two() {
+            return Ok(Bounded { cell, bound });
+        }
+
+        let offset = F::from_u128((1u128 << num_bits) - bound as u128);
+        let w = layouter.assign_region(
+            || "v + 2^n - bound",
+            |mut region| {
+                self.q_offset.enable(&mut region, 0)?;
+                region.assign_fixed(|| "2^n - bound", self.offset, 0, || Value::known(offset))?;
+
+                let v = cell.copy_advice(|| "v", &mut region, self.value, 0)?;
+                region.assign_advice(
+                    || "w",
+                    self.value,
+                    1,
+                    || v.value().copied() + Value::known(Assigned::from(offset)),
+                )
+            },
+        )?;
+        self.running_sum
+            .copy_check(layouter.namespace(|| "w < 2^n"), &w, num_bits)?;
+
+        Ok(Bounded { cell, bound })
+    }
+}
+
+/// Checks each value against its own bound with one gate and one table.
+pub struct MyCircuit<F: FieldExt, const LOOKUP_RANGE: usize> {
+    values: Vec<(Value<Assigned<F>>, u64)>,
+}
+
+impl<F: FieldExt, const LOOKUP_RANGE: usize> MyCircuit<F, LOOKUP_RANGE> {
+    /// Each value is checked to be in `0..bound`.
+    pub fn new(values: Vec<(Value<Assigned<F>>, u64)>) -> Self {
+        Self { values }
+    }
+}
+
+impl<F: FieldExt, const LOOKUP_RANGE: usize> Circuit<F> for MyCircuit<F, LOOKUP_RANGE> {
+    type Config = DynamicRangeConfig<F, LOOKUP_RANGE>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(
+            self.values
+                .iter()
+                .map(|(_, bound)| (Value::unknown(), *bound))
+                .collect(),
+        )
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        let table = RangeTableConfig::configure(meta);
+        DynamicRangeConfig::configure(meta, value, table)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.running_sum.table.load(&mut layouter)?;
+
+        for (value, bound) in &self.values {
+            config.witness_check(layouter.namespace(|| "value"), *value, *bound)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn e8_dynamic_rangecheck() {
+    use crate::soundness::mock_verify;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const LOOKUP_RANGE: usize = 256;
+    let k = 9;
+    let check = |values: &[(u64, u64)]| {
+        let values = values
+            .iter()
+            .map(|(v, bound)| (Value::known(Fp::from(*v).into()), *bound))
+            .collect();
+        let circuit = MyCircuit::<Fp, LOOKUP_RANGE>::new(values);
+        mock_verify(k, &circuit, vec![])
+    };
+
+    // 2のべきのboundも、そうでないboundも1つの回路で
+    let bounds = [1, 2, 8, 10, 100, 256, 1000, 1 << 40, u64::MAX];
+    let honest = bounds
+        .iter()
+        .map(|bound| (bound - 1, *bound))
+        .collect::<Vec<_>>();
+    assert_eq!(check(&honest), Ok(()));
+
+    for bound in bounds {
+        assert_eq!(check(&[(0, bound)]), Ok(()), "0 < {}", bound);
+        assert!(
+            check(&[(bound, bound)]).is_err(),
+            "{} < {} accepted",
+            bound,
+            bound
+        );
+    }
+    // 2^n - 1 はnビットに入るけど、boundより大きい
+    assert!(check(&[(127, 100)]).is_err());
+    assert!(check(&[(1023, 1000)]).is_err());
+
+    // bound = 0 には入る値がない
+    let circuit = MyCircuit::<Fp, LOOKUP_RANGE>::new(vec![(Value::known(Fp::zero().into()), 0)]);
+    assert!(matches!(
+        MockProver::run(k, &circuit, vec![]),
+        Err(Error::Synthesis)
+    ));
+}
+
+#[test]
+fn e8_dynamic_rangecheck_soundness() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::pasta::Fp;
+
+    let circuit = MyCircuit::<Fp, 256>::new(vec![
+        (Value::known(Fp::from(99).into()), 100),
+        (Value::known(Fp::from(255).into()), 256),
+    ]);
+    assert_sound(9, &circuit, vec![]);
+}
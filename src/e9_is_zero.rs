@@ -0,0 +1,467 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression,
+        Instance, Selector,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+// valueが0かどうかを表すbooleanのcell。invはvalueの逆数 (value = 0なら何でもいい)。
+//
+//   out = 1 - value * inv
+//   value * out = 0
+//
+// value != 0 なら2つ目からout = 0、1つ目からinv = 1 / valueしかない。
+// value = 0 なら1つ目からout = 1で、invは何を入れても結果は変わらない。
+//
+//   value | inv | out | q_is_zero
+//  -------+-----+-----+----------
+//     v   | 1/v |  0  |    1
+//
+// IsEqualはvalueのところをa - bにしたもの。
+//
+//   a | b | inv | out | q_is_equal
+
+/// The two IsZero constraints for `value`, shared by IsZero and IsEqual.
+fn is_zero_constraints<F: FieldExt>(
+    value: Expression<F>,
+    inv: Expression<F>,
+    out: Expression<F>,
+) -> [(&'static str, Expression<F>); 2] {
+    let one = Expression::Constant(F::one());
+    [
+        (
+            "out = 1 - value * inv",
+            out.clone() - (one - value.clone() * inv),
+        ),
+        ("value * out = 0", value * out),
+    ]
+}
+
+/// `(inv, out)` for `value`.
+fn is_zero_witness<F: FieldExt>(value: Value<F>) -> (Value<F>, Value<F>) {
+    value
+        .map(|v| {
+            let inv = v.invert().unwrap_or_else(F::zero);
+            (inv, F::one() - v * inv)
+        })
+        .unzip()
+}
+
+#[derive(Debug, Clone)]
+pub struct IsZeroConfig {
+    value: Column<Advice>,
+    inv: Column<Advice>,
+    out: Column<Advice>,
+    selector: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsZeroChip<F: FieldExt> {
+    config: IsZeroConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> IsZeroChip<F> {
+    pub fn construct(config: IsZeroConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> IsZeroConfig {
+        let [value, inv, out] = advice;
+        let selector = meta.selector();
+
+        meta.enable_equality(value);
+        meta.enable_equality(out);
+
+        meta.create_gate("is zero", |meta| {
+            let q = meta.query_selector(selector);
+            let value = meta.query_advice(value, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            Constraints::with_selector(q, is_zero_constraints(value, inv, out))
+        });
+
+        IsZeroConfig {
+            value,
+            inv,
+            out,
+            selector,
+        }
+    }
+
+    /// A boolean cell that is 1 iff `value` is 0.
+    ///
+    /// `value` can come from any chip, e.g. a `TutorialCell` or a Fibonacci
+    /// term; it is copied in.
+    pub fn assign<V>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<V, F>,
+    ) -> Result<AssignedCell<F, F>, Error>
+    where
+        V: Clone,
+        for<'v> Assigned<F>: From<&'v V>,
+    {
+        let config = &self.config;
+        layouter.assign_region(
+            || "is zero",
+            |mut region| {
+                config.selector.enable(&mut region, 0)?;
+                let value = value.copy_advice(|| "value", &mut region, config.value, 0)?;
+
+                let (inv, out) =
+                    is_zero_witness(value.value().map(|v| Assigned::from(v).evaluate()));
+                region.assign_advice(|| "inv", config.inv, 0, || inv)?;
+                region.assign_advice(|| "out", config.out, 0, || out)
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IsEqualConfig {
+    lhs: Column<Advice>,
+    rhs: Column<Advice>,
+    inv: Column<Advice>,
+    out: Column<Advice>,
+    selector: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsEqualChip<F: FieldExt> {
+    config: IsEqualConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> IsEqualChip<F> {
+    pub fn construct(config: IsEqualConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 4]) -> IsEqualConfig {
+        let [lhs, rhs, inv, out] = advice;
+        let selector = meta.selector();
+
+        meta.enable_equality(lhs);
+        meta.enable_equality(rhs);
+        meta.enable_equality(out);
+
+        meta.create_gate("is equal", |meta| {
+            let q = meta.query_selector(selector);
+            let lhs = meta.query_advice(lhs, Rotation::cur());
+            let rhs = meta.query_advice(rhs, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            Constraints::with_selector(q, is_zero_constraints(lhs - rhs, inv, out))
+        });
+
+        IsEqualConfig {
+            lhs,
+            rhs,
+            inv,
+            out,
+            selector,
+        }
+    }
+
+    /// A boolean cell that is 1 iff `a` and `b` hold the same value.
+    pub fn assign<V, W>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<V, F>,
+        b: &AssignedCell<W, F>,
+    ) -> Result<AssignedCell<F, F>, Error>
+    where
+        V: Clone,
+        W: Clone,
+        for<'v> Assigned<F>: From<&'v V> + From<&'v W>,
+    {
+        let config = &self.config;
+        layouter.assign_region(
+            || "is equal",
+            |mut region| {
+                config.selector.enable(&mut region, 0)?;
+                let a = a.copy_advice(|| "a", &mut region, config.lhs, 0)?;
+                let b = b.copy_advice(|| "b", &mut region, config.rhs, 0)?;
+
+                let diff = a
+                    .value()
+                    .zip(b.value())
+                    .map(|(a, b)| Assigned::from(a).evaluate() - Assigned::from(b).evaluate());
+                let (inv, out) = is_zero_witness(diff);
+                region.assign_advice(|| "inv", config.inv, 0, || inv)?;
+                region.assign_advice(|| "out", config.out, 0, || out)
+            },
+        )
+    }
+}
+
+/// Exposes `[value == 0, a == b]`.
+#[derive(Default)]
+pub struct MyCircuit<F> {
+    value: Value<F>,
+    a: Value<F>,
+    b: Value<F>,
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    pub fn new(value: Value<F>, a: Value<F>, b: Value<F>) -> Self {
+        Self { value, a, b }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IsZeroCircuitConfig {
+    is_zero: IsZeroConfig,
+    is_equal: IsEqualConfig,
+    witness: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = IsZeroCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let is_zero = IsZeroChip::configure(meta, [advice[0], advice[1], advice[2]]);
+        let is_equal = IsEqualChip::configure(meta, advice);
+
+        let witness = meta.advice_column();
+        meta.enable_equality(witness);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        IsZeroCircuitConfig {
+            is_zero,
+            is_equal,
+            witness,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (value, a, b) = layouter.assign_region(
+            || "witnesses",
+            |mut region| {
+                let value = region.assign_advice(|| "value", config.witness, 0, || self.value)?;
+                let a = region.assign_advice(|| "a", config.witness, 1, || self.a)?;
+                let b = region.assign_advice(|| "b", config.witness, 2, || self.b)?;
+                Ok((value, a, b))
+            },
+        )?;
+
+        let is_zero = IsZeroChip::construct(config.is_zero)
+            .assign(layouter.namespace(|| "value == 0"), &value)?;
+        let is_equal = IsEqualChip::construct(config.is_equal).assign(
+            layouter.namespace(|| "a == b"),
+            &a,
+            &b,
+        )?;
+
+        layouter.constrain_instance(is_zero.cell(), config.instance, 0)?;
+        layouter.constrain_instance(is_equal.cell(), config.instance, 1)
+    }
+}
+
+#[test]
+fn e9_is_zero() {
+    use crate::soundness::assert_sound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    let k = 4;
+    let bit = |b: bool| Fp::from(b as u64);
+    let values = [Fp::zero(), Fp::one(), Fp::from(5), -Fp::one()];
+
+    for value in values {
+        for (a, b) in [
+            (Fp::zero(), Fp::zero()),
+            (Fp::from(7), Fp::from(7)),
+            (Fp::from(7), -Fp::from(7)),
+        ] {
+            let circuit = MyCircuit::new(Value::known(value), Value::known(a), Value::known(b));
+            let expected = vec![bit(value == Fp::zero()), bit(a == b)];
+
+            let prover = MockProver::run(k, &circuit, vec![expected.clone()]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            for row in 0..2 {
+                let mut wrong = expected.clone();
+                wrong[row] = Fp::one() - wrong[row];
+                let prover = MockProver::run(k, &circuit, vec![wrong]).unwrap();
+                assert!(prover.verify().is_err());
+            }
+        }
+    }
+
+    let circuit = MyCircuit::new(
+        Value::known(Fp::from(3)),
+        Value::known(Fp::from(4)),
+        Value::known(Fp::from(4)),
+    );
+    assert_sound(k, &circuit, vec![vec![Fp::zero(), Fp::one()]]);
+}
+
+// invを好きに選べるproverがIsZeroの結果を変えられないこと
+#[test]
+fn e9_is_zero_malicious_inverse() {
+    use halo2_proofs::{arithmetic::Field, dev::MockProver, pasta::Fp};
+
+    struct Malicious {
+        value: Fp,
+        inv: Fp,
+        out: Fp,
+    }
+
+    impl Circuit<Fp> for Malicious {
+        type Config = IsZeroConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { ..*self }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            IsZeroChip::configure(meta, advice)
+        }
+
+        // chipのassignを通さずに、好きな値を入れる
+        fn synthesize(
+            &self,
+            config: IsZeroConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "malicious is zero",
+                |mut region| {
+                    config.selector.enable(&mut region, 0)?;
+                    region.assign_advice(
+                        || "value",
+                        config.value,
+                        0,
+                        || Value::known(self.value),
+                    )?;
+                    region.assign_advice(|| "inv", config.inv, 0, || Value::known(self.inv))?;
+                    region.assign_advice(|| "out", config.out, 0, || Value::known(self.out))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    let run = |value: u64, inv: Fp, out: u64| {
+        let circuit = Malicious {
+            value: Fp::from(value),
+            inv,
+            out: Fp::from(out),
+        };
+        MockProver::run(3, &circuit, vec![]).unwrap().verify()
+    };
+    let five_inv = Fp::from(5).invert().unwrap();
+
+    assert_eq!(run(5, five_inv, 0), Ok(()));
+    // 0ならinvは何でもいい
+    assert_eq!(run(0, Fp::zero(), 1), Ok(()));
+    assert_eq!(run(0, Fp::from(42), 1), Ok(()));
+
+    // 5を0と言い張る: invを0にしてout = 1
+    assert!(run(5, Fp::zero(), 1).is_err());
+    // 逆数をずらしてoutを0でも1でもない値にする
+    assert!(run(5, Fp::from(2), 0).is_err());
+    assert!(run(5, Fp::from(2), 1).is_err());
+    // 0を0でないと言い張る
+    assert!(run(0, Fp::from(42), 0).is_err());
+}
+
+// TutorialChipのcellとFibonacciのcellをそのまま比べる
+#[test]
+fn e9_is_equal_with_other_chips() {
+    use crate::e0_tutorial_answer::{TutorialChip, TutorialConfig};
+    use crate::e3_fibonacci_ex2::{FibonacciChip, FibonacciConfig};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // x * x == F(n) かどうか
+    struct SquareIsFibonacci {
+        x: Value<Fp>,
+        n: usize,
+    }
+
+    impl Circuit<Fp> for SquareIsFibonacci {
+        type Config = (TutorialConfig, FibonacciConfig, IsEqualConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                x: Value::unknown(),
+                n: self.n,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let tutorial = TutorialChip::configure(meta);
+            let advice = meta.advice_column();
+            let fibonacci = FibonacciChip::configure(meta, advice, tutorial.PI);
+            let advice = [(); 4].map(|_| meta.advice_column());
+            let is_equal = IsEqualChip::configure(meta, advice);
+            (tutorial, fibonacci, is_equal)
+        }
+
+        fn synthesize(
+            &self,
+            (tutorial, fibonacci, is_equal): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let square = TutorialChip::new(tutorial.clone()).assign_packed(
+                &mut layouter,
+                "square",
+                |b| {
+                    let x = b.load_private(self.x)?;
+                    b.mul(&x, &x)
+                },
+            )?;
+            let term = FibonacciChip::construct(fibonacci).assign(
+                layouter.namespace(|| "fibonacci"),
+                self.n,
+                Some((Fp::zero(), Fp::one())),
+            )?;
+
+            let out = IsEqualChip::construct(is_equal).assign(
+                layouter.namespace(|| "x * x == F(n)"),
+                &square,
+                &term,
+            )?;
+            layouter.constrain_instance(out.cell(), tutorial.PI, 0)
+        }
+    }
+
+    // 0, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55, 89, 144
+    for (x, n, expected) in [(12, 12, true), (11, 12, false), (1, 2, true), (3, 8, false)] {
+        let circuit = SquareIsFibonacci {
+            x: Value::known(Fp::from(x)),
+            n,
+        };
+        let prover = MockProver::run(5, &circuit, vec![vec![Fp::from(expected as u64)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()), "{}^2 == F({})", x, n);
+    }
+}
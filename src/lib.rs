@@ -3,7 +3,20 @@ mod e1_tutorial_practice;
 mod e2_vitalik_example;
 mod e3_fibonacci_ex1;
 mod e3_fibonacci_ex2;
+mod e3_fibonacci_matrix;
+mod e3_fibonacci_private_index;
+mod e3_linear_recurrence;
 mod e4_simple_rangecheck;
 mod e5_lookup_rangecheck;
+mod e7_running_sum_rangecheck;
+mod e8_dynamic_rangecheck;
+mod e9_is_zero;
+mod e10_bits;
 
 mod e6_poseidon_test;
+mod e11_dynamic_lookup;
+
+pub mod chips;
+pub mod prover;
+pub mod report;
+pub mod soundness;
@@ -0,0 +1,474 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    ops::{Add, Mul, Neg},
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use halo2_examples::{
+    chips::{fibonacci, plonk, poseidon, range},
+    prover, report,
+};
+use halo2_proofs::{
+    circuit::Value,
+    dev::{CircuitCost, MockProver},
+    pasta::{Eq, Fp},
+    plonk::{Assigned, Circuit, ConstraintSystem},
+};
+
+type BoxResult<T> = Result<T, Box<dyn Error>>;
+
+// e4/e5のRANGEはconst genericなので、CLIではテストと同じ値に固定する
+const RANGE: usize = 8;
+const LOOKUP_RANGE: usize = 256;
+
+/// Mock, prove, verify and render the example circuits.
+#[derive(Parser)]
+#[command(name = "halo2-examples")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs the MockProver and prints every failing constraint.
+    Mock(CircuitArgs),
+    /// Creates a real proof and writes it to `--proof`.
+    Prove {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        #[arg(long, default_value = "proof.bin")]
+        proof: PathBuf,
+    },
+    /// Verifies a proof written by `prove` against the public inputs.
+    Verify {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        #[arg(long, default_value = "proof.bin")]
+        proof: PathBuf,
+    },
+    /// Renders the circuit layout to a PNG (needs the `dev-graph` feature).
+    Layout {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        #[arg(long, default_value = "layout.png")]
+        output: PathBuf,
+    },
+    /// Prints the shape of the constraint system and the estimated proof size.
+    Cost(CircuitArgs),
+    /// Compares the multi-column and single-column Fibonacci layouts.
+    Report {
+        /// The Fibonacci terms F(n) to prove with both layouts.
+        #[arg(short = 'n', long, value_delimiter = ',', default_value = "9,100,1000")]
+        lengths: Vec<usize>,
+    },
+}
+
+/// What to do with a circuit, i.e. every command but `report`.
+enum Action<'a> {
+    Mock,
+    Prove(&'a Path),
+    Verify(&'a Path),
+    Layout(&'a Path),
+    Cost,
+}
+
+impl Action<'_> {
+    /// Only the prover needs the private witnesses; the other actions work on
+    /// the circuit shape and the public inputs.
+    fn needs_witness(&self) -> bool {
+        matches!(self, Action::Mock | Action::Prove(_))
+    }
+}
+
+#[derive(Args)]
+struct CircuitArgs {
+    #[arg(value_enum)]
+    circuit: CircuitName,
+    /// The circuit has 2^k rows. Defaults to the smallest k that fits the
+    /// Fibonacci circuits, and to the value used in the tests otherwise.
+    #[arg(short, long)]
+    k: Option<u32>,
+    /// A witness or public input, e.g. `--input x=5`. Values are field
+    /// elements in decimal or `0x` hexadecimal, optionally negative.
+    #[arg(short, long = "input", value_name = "NAME=VALUE")]
+    inputs: Vec<String>,
+    /// A file with one `NAME=VALUE` per line. `--input` takes precedence.
+    #[arg(long, value_name = "PATH")]
+    inputs_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CircuitName {
+    /// e0: x^2 * y^2 + constant = z. Inputs: x, y, constant, (z)
+    Tutorial,
+    /// e2: x^3 + x + constant = z. Inputs: x, constant, (z)
+    Vitalik,
+    /// e3 ex1: F(n) in three columns, n >= 2, n = 9 by default. Inputs: a, b, (n), (out)
+    FibonacciMulti,
+    /// e3 ex2: F(n) in one column, n = 9 by default. Inputs: a, b, (n), (out)
+    FibonacciSingle,
+    /// e3 matrix: F(n) in O(log n) rows. Inputs: a, b, n, (bits), (out)
+    FibonacciMatrix,
+    /// e4: value < 8 with a polynomial gate. Inputs: value
+    RangeSimple,
+    /// e5: value < 8 and lookup_value < 256. Inputs: value, lookup_value
+    RangeLookup,
+    /// e6: Poseidon hash of two bn256 elements (mock only). Inputs: m0, m1, (output)
+    Poseidon,
+}
+
+impl CircuitName {
+    fn default_k(self) -> u32 {
+        match self {
+            CircuitName::RangeLookup => 9,
+            CircuitName::Poseidon => 6,
+            _ => 4,
+        }
+    }
+}
+
+/// The field operations `parse_field` needs, so that it works for both the
+/// pasta `Fp` and the bn256 `Fr` of the other halo2.
+trait InputField:
+    Copy + From<u64> + Add<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+}
+
+impl<F> InputField for F where
+    F: Copy + From<u64> + Add<Output = F> + Mul<Output = F> + Neg<Output = F>
+{
+}
+
+/// `NAME=VALUE` pairs from the command line and the inputs file.
+struct Inputs {
+    values: HashMap<String, String>,
+    needs_witness: bool,
+}
+
+impl Inputs {
+    fn parse(args: &CircuitArgs, needs_witness: bool) -> BoxResult<Self> {
+        let mut values = HashMap::new();
+
+        if let Some(path) = &args.inputs_file {
+            let contents = fs::read_to_string(path)?;
+            for line in contents.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (name, value) = parse_pair(line)?;
+                values.insert(name, value);
+            }
+        }
+        for pair in &args.inputs {
+            let (name, value) = parse_pair(pair)?;
+            values.insert(name, value);
+        }
+
+        Ok(Self {
+            values,
+            needs_witness,
+        })
+    }
+
+    fn optional<F: InputField>(&self, name: &str) -> BoxResult<Option<F>> {
+        self.values
+            .get(name)
+            .map(|value| {
+                parse_field(value)
+                    .ok_or_else(|| format!("invalid value for `{}`: `{}`", name, value).into())
+            })
+            .transpose()
+    }
+
+    fn required<F: InputField>(&self, name: &str) -> BoxResult<F> {
+        self.optional(name)?
+            .ok_or_else(|| format!("missing input `{}` (pass --input {}=VALUE)", name, name).into())
+    }
+
+    /// A length or bit width rather than a field element.
+    fn count(&self, name: &str) -> BoxResult<Option<u64>> {
+        self.values
+            .get(name)
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(|e| format!("invalid value for `{}`: {}", name, e).into())
+            })
+            .transpose()
+    }
+
+    /// A private witness. Verifying, rendering and costing run without it.
+    fn witness(&self, name: &str) -> BoxResult<Option<Fp>> {
+        if self.needs_witness {
+            self.required(name).map(Some)
+        } else {
+            self.optional(name)
+        }
+    }
+
+    /// A public input, either given explicitly or computed from the witnesses.
+    fn public(&self, name: &str, computed: Option<Fp>) -> BoxResult<Fp> {
+        match self.optional(name)? {
+            Some(v) => Ok(v),
+            None => computed.ok_or_else(|| {
+                format!(
+                    "missing input `{}`; pass it or the witnesses it is computed from",
+                    name
+                )
+                .into()
+            }),
+        }
+    }
+}
+
+fn parse_pair(pair: &str) -> BoxResult<(String, String)> {
+    let (name, value) = pair
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got `{}`", pair))?;
+
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+/// Parses a decimal or `0x` hexadecimal field element, optionally negative.
+/// Values past the modulus wrap around.
+fn parse_field<F: InputField>(value: &str) -> Option<F> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let (radix, digits) = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(digits) => (16, digits),
+        None => (10, value),
+    };
+    if digits.is_empty() {
+        return None;
+    }
+
+    let value = digits.chars().try_fold(F::from(0), |acc, c| {
+        c.to_digit(radix)
+            .map(|d| acc * F::from(radix as u64) + F::from(d as u64))
+    })?;
+    Some(if negative { -value } else { value })
+}
+
+fn known(value: Option<Fp>) -> Value<Fp> {
+    value.map(Value::known).unwrap_or_else(Value::unknown)
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(&cli.command) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(command: &Command) -> BoxResult<()> {
+    // reportは自分で回路を作るので、ここで分けておく
+    let (args, action) = match command {
+        Command::Report { lengths } => return run_report(lengths),
+        Command::Mock(args) => (args, Action::Mock),
+        Command::Prove { circuit, proof } => (circuit, Action::Prove(proof)),
+        Command::Verify { circuit, proof } => (circuit, Action::Verify(proof)),
+        Command::Layout { circuit, output } => (circuit, Action::Layout(output)),
+        Command::Cost(args) => (args, Action::Cost),
+    };
+    let inputs = Inputs::parse(args, action.needs_witness())?;
+    let k = args.k.unwrap_or_else(|| args.circuit.default_k());
+
+    match args.circuit {
+        CircuitName::Tutorial => {
+            let constant: Fp = inputs.required("constant")?;
+            let x = inputs.witness("x")?;
+            let y = inputs.witness("y")?;
+            let z = inputs.public(
+                "z",
+                x.zip(y).map(|(x, y)| x * x * y * y + constant),
+            )?;
+
+            let circuit = plonk::TutorialCircuit::new(known(x), known(y), constant);
+            run_circuit(&action, k, circuit, vec![vec![constant, z]])
+        }
+        CircuitName::Vitalik => {
+            let constant: Fp = inputs.required("constant")?;
+            let x = inputs.witness("x")?;
+            let z = inputs.public("z", x.map(|x| x * x * x + x + constant))?;
+
+            let circuit = plonk::VitalikCircuit::new(known(x), constant);
+            run_circuit(&action, k, circuit, vec![vec![constant, z]])
+        }
+        CircuitName::FibonacciMulti => {
+            let a: Fp = inputs.required("a")?;
+            let b: Fp = inputs.required("b")?;
+            let n = inputs.count("n")?.unwrap_or(9) as usize;
+            let out = inputs.public("out", Some(fibonacci::fibonacci(a, b, n)))?;
+
+            let circuit = fibonacci::multi_column::FibonacciCircuit::<Fp>::new(n, None);
+            let k = args.k.unwrap_or_else(|| circuit.min_k());
+            run_circuit(&action, k, circuit, vec![vec![a, b, out]])
+        }
+        CircuitName::FibonacciSingle => {
+            let a: Fp = inputs.required("a")?;
+            let b: Fp = inputs.required("b")?;
+            let n = inputs.count("n")?.unwrap_or(9) as usize;
+            let out = inputs.public("out", Some(fibonacci::fibonacci(a, b, n)))?;
+
+            let circuit = fibonacci::single_column::FibonacciCircuit::<Fp>::new(n, None);
+            let k = args.k.unwrap_or_else(|| circuit.min_k());
+            run_circuit(&action, k, circuit, vec![vec![a, b, out]])
+        }
+        CircuitName::FibonacciMatrix => {
+            let a: Fp = inputs.required("a")?;
+            let b: Fp = inputs.required("b")?;
+            let n = inputs
+                .count("n")?
+                .ok_or("missing input `n` (pass --input n=VALUE)")?;
+            // bitsで回路の形が決まるので、verifyでも同じ値を渡す
+            let num_bits = match inputs.count("bits")? {
+                Some(bits) => bits as usize,
+                None => (64 - n.leading_zeros() as usize).max(1),
+            };
+            let mut instance = fibonacci::matrix::FibonacciMatrixCircuit::instance(a, b, n);
+            instance[3] = inputs.public("out", Some(instance[3]))?;
+
+            let circuit =
+                fibonacci::matrix::FibonacciMatrixCircuit::<Fp>::new(num_bits, Value::known(n));
+            let k = args.k.unwrap_or_else(|| circuit.min_k());
+            run_circuit(&action, k, circuit, vec![instance])
+        }
+        CircuitName::RangeSimple => {
+            let value: Value<Assigned<Fp>> = known(inputs.witness("value")?).into();
+
+            let circuit = range::simple::RangeCheckCircuit::<Fp, RANGE>::new(value);
+            run_circuit(&action, k, circuit, vec![])
+        }
+        CircuitName::RangeLookup => {
+            let value: Value<Assigned<Fp>> = known(inputs.witness("value")?).into();
+            let lookup_value: Value<Assigned<Fp>> = known(inputs.witness("lookup_value")?).into();
+
+            let circuit = range::lookup::RangeCheckCircuit::<Fp, RANGE, LOOKUP_RANGE>::new(
+                value,
+                lookup_value,
+            );
+            run_circuit(&action, k, circuit, vec![])
+        }
+        CircuitName::Poseidon => run_poseidon(&action, k, &inputs),
+    }
+}
+
+fn run_circuit<C: Circuit<Fp>>(
+    action: &Action,
+    k: u32,
+    circuit: C,
+    instances: Vec<Vec<Fp>>,
+) -> BoxResult<()> {
+    let instance_refs: Vec<&[Fp]> = instances.iter().map(|column| column.as_slice()).collect();
+
+    match action {
+        Action::Mock => {
+            let prover = MockProver::run(k, &circuit, instances.clone())?;
+            if let Err(failures) = prover.verify() {
+                for failure in &failures {
+                    eprintln!("{}", failure);
+                }
+                return Err(format!("{} constraint(s) not satisfied", failures.len()).into());
+            }
+            println!("mock proof verified");
+        }
+        Action::Prove(proof) => {
+            let (params, pk) = prover::setup(k, &circuit)?;
+            let bytes = prover::prove(&params, &pk, circuit, &instance_refs)?;
+            fs::write(proof, &bytes)?;
+            println!("wrote {} byte proof to {}", bytes.len(), proof.display());
+        }
+        Action::Verify(proof) => {
+            // IPAのparamsとkeyはkと回路の形から決定的に作り直せる
+            let (params, pk) = prover::setup(k, &circuit)?;
+            let bytes = fs::read(proof)?;
+            prover::verify(&params, pk.get_vk(), &bytes, &instance_refs)?;
+            println!("proof verified");
+        }
+        Action::Layout(output) => {
+            render_layout(k, &circuit, output)?;
+            println!("wrote layout to {}", output.display());
+        }
+        Action::Cost => {
+            let mut cs = ConstraintSystem::<Fp>::default();
+            C::configure(&mut cs);
+            println!("advice columns:   {}", cs.num_advice_columns());
+            println!("fixed columns:    {}", cs.num_fixed_columns());
+            println!("instance columns: {}", cs.num_instance_columns());
+            println!("selectors:        {}", cs.num_selectors());
+            println!("gate degree:      {}", cs.degree());
+
+            let cost = CircuitCost::<Eq, C>::measure(k as usize, &circuit);
+            println!("proof size:       {} bytes", usize::from(cost.proof_size(1)));
+        }
+    }
+
+    Ok(())
+}
+
+fn run_report(lengths: &[usize]) -> BoxResult<()> {
+    println!("{}", report::CostReport::header());
+    for report in report::fibonacci_report(lengths)? {
+        println!("{}", report);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "dev-graph")]
+fn render_layout<C: Circuit<Fp>>(k: u32, circuit: &C, output: &Path) -> BoxResult<()> {
+    use plotters::prelude::*;
+
+    let root = BitMapBackend::new(output, (1024, 3096)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let title = output.file_stem().and_then(|s| s.to_str()).unwrap_or("layout");
+    let root = root.titled(title, ("sans-serif", 60))?;
+
+    halo2_proofs::dev::CircuitLayout::default().render(k, circuit, &root)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "dev-graph"))]
+fn render_layout<C: Circuit<Fp>>(_k: u32, _circuit: &C, _output: &Path) -> BoxResult<()> {
+    Err("layout needs the `dev-graph` feature: cargo run --features dev-graph -- layout ...".into())
+}
+
+/// Poseidon lives on bn256 through `halo2-base`, so only the MockProver of that
+/// halo2 fork is wired up here.
+fn run_poseidon(action: &Action, k: u32, inputs: &Inputs) -> BoxResult<()> {
+    use halo2_base::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+    use poseidon_circuit::poseidon::primitives::{ConstantLength, Hash, P128Pow5T3};
+
+    if !matches!(action, Action::Mock) {
+        return Err("the poseidon circuit only supports `mock`".into());
+    }
+
+    let message: [Fr; 2] = [inputs.required("m0")?, inputs.required("m1")?];
+    let output = match inputs.optional("output")? {
+        Some(v) => v,
+        None => Hash::<_, P128Pow5T3<Fr>, ConstantLength<2>, 3, 2>::init().hash(message),
+    };
+
+    let circuit =
+        poseidon::HashCircuit::<P128Pow5T3<Fr>, 3, 2, 2>::new(Some(message), Some(output));
+    let prover = MockProver::run(k, &circuit, vec![])?;
+    if let Err(failures) = prover.verify() {
+        for failure in &failures {
+            eprintln!("{}", failure);
+        }
+        return Err(format!("{} constraint(s) not satisfied", failures.len()).into());
+    }
+    println!("mock proof verified");
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey,
+        SingleVerifier, VerifyingKey,
+    },
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand::rngs::OsRng;
+
+// MockProverは制約を直接チェックするだけなので、ここでは本物のProveとVerifyをする。
+// IPA (pasta curves) なのでtrusted setupは要らなくて、paramsはkから決定的に作れる。
+//
+//   setup:  Params::new(k) -> keygen_vk -> keygen_pk
+//   prove:  create_proof (Blake2b transcriptにproofを書き込む)
+//   verify: verify_proof (同じpublic inputsでtranscriptを読む)
+
+/// Generates the commitment parameters and the proving key for `circuit`.
+///
+/// Keys only depend on the shape of the circuit, so the witnesses are stripped
+/// with `without_witnesses` before running keygen.
+pub fn setup<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+) -> Result<(Params<EqAffine>, ProvingKey<EqAffine>), Error> {
+    let params: Params<EqAffine> = Params::new(k);
+    let empty_circuit = circuit.without_witnesses();
+
+    let vk = keygen_vk(&params, &empty_circuit)?;
+    let pk = keygen_pk(&params, vk, &empty_circuit)?;
+
+    Ok((params, pk))
+}
+
+/// Creates a proof for `circuit`.
+///
+/// `instances` holds the values of every instance column, in the order the
+/// columns were created in `configure`.
+pub fn prove<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    instances: &[&[Fp]],
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof(params, pk, &[circuit], &[instances], OsRng, &mut transcript)?;
+
+    Ok(transcript.finalize())
+}
+
+/// Checks `proof` against the verifying key and the same instance values that
+/// were used when proving.
+pub fn verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    instances: &[&[Fp]],
+) -> Result<(), Error> {
+    let strategy = SingleVerifier::new(params);
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+
+    verify_proof(params, vk, strategy, &[instances], &mut transcript)
+}
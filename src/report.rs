@@ -0,0 +1,341 @@
+//! Layout and cost comparison between circuits that prove the same statement.
+//!
+//! `e3_fibonacci_ex1` puts each term in its own region over three advice
+//! columns, `e3_fibonacci_ex2` keeps the whole table in one column and reaches
+//! the previous terms with `Rotation`. [`fibonacci_report`] lays both out for a
+//! few sequence lengths and measures what each choice costs.
+
+use std::{
+    fmt,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Value,
+    dev::CircuitCost,
+    pasta::{Eq, Fp},
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+
+use crate::{
+    e3_fibonacci_ex1::MyCircuit as MultiColumn, e3_fibonacci_ex2::MyCircuit as SingleColumn, prover,
+};
+
+/// The shape, size and timings of one circuit.
+#[derive(Debug, Clone)]
+pub struct CostReport {
+    pub name: String,
+    /// The statement size, e.g. the index of the Fibonacci term.
+    pub n: usize,
+    pub k: u32,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+    /// Rows the floor planner actually writes to, out of the `2^k` available.
+    pub rows: usize,
+    pub degree: usize,
+    /// Columns taking part in the permutation argument.
+    pub permutation_columns: usize,
+    /// Estimated by `CircuitCost` for a single proof.
+    pub proof_size: usize,
+    pub prove_time: Duration,
+    pub verify_time: Duration,
+}
+
+impl CostReport {
+    /// Lays out `circuit`, estimates its proof size, then creates and verifies
+    /// a real proof against `instance` to time it.
+    pub fn measure<C: Circuit<Fp>>(
+        name: &str,
+        n: usize,
+        k: u32,
+        circuit: C,
+        instance: &[Fp],
+    ) -> Result<Self, Error> {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let config = C::configure(&mut cs);
+
+        let mut rows = RowCounter {
+            rows: 0,
+            _marker: PhantomData,
+        };
+        C::FloorPlanner::synthesize(&mut rows, &circuit, config, cs.constants().clone())?;
+
+        let cost = CircuitCost::<Eq, C>::measure(k as usize, &circuit);
+        let proof_size = usize::from(cost.proof_size(1));
+
+        let (params, pk) = prover::setup(k, &circuit)?;
+        let start = Instant::now();
+        let proof = prover::prove(&params, &pk, circuit, &[instance])?;
+        let prove_time = start.elapsed();
+
+        let start = Instant::now();
+        prover::verify(&params, pk.get_vk(), &proof, &[instance])?;
+        let verify_time = start.elapsed();
+
+        Ok(Self {
+            name: name.to_string(),
+            n,
+            k,
+            advice_columns: cs.num_advice_columns(),
+            fixed_columns: cs.num_fixed_columns(),
+            instance_columns: cs.num_instance_columns(),
+            selectors: cs.num_selectors(),
+            rows: rows.rows,
+            degree: cs.degree(),
+            permutation_columns: cs.permutation().get_columns().len(),
+            proof_size,
+            prove_time,
+            verify_time,
+        })
+    }
+
+    /// The header matching the `Display` output of a report.
+    pub fn header() -> String {
+        format!(
+            "{:<14} {:>6} {:>3} {:>7} {:>6} {:>9} {:>9} {:>7} {:>6} {:>7} {:>11} {:>11} {:>11}",
+            "circuit",
+            "n",
+            "k",
+            "advice",
+            "fixed",
+            "instance",
+            "selectors",
+            "rows",
+            "degree",
+            "perm",
+            "proof (B)",
+            "prove",
+            "verify",
+        )
+    }
+}
+
+impl fmt::Display for CostReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<14} {:>6} {:>3} {:>7} {:>6} {:>9} {:>9} {:>7} {:>6} {:>7} {:>11} {:>11} {:>11}",
+            self.name,
+            self.n,
+            self.k,
+            self.advice_columns,
+            self.fixed_columns,
+            self.instance_columns,
+            self.selectors,
+            self.rows,
+            self.degree,
+            self.permutation_columns,
+            self.proof_size,
+            format!("{:.2?}", self.prove_time),
+            format!("{:.2?}", self.verify_time),
+        )
+    }
+}
+
+/// One line of a report: a measured circuit, or a circuit that cannot prove
+/// the statement, e.g. `e3_fibonacci_ex1` for `n < 2`.
+#[derive(Debug, Clone)]
+pub enum ReportRow {
+    Measured(CostReport),
+    NotApplicable { name: String, n: usize },
+}
+
+impl ReportRow {
+    /// Measures `circuit` like [`CostReport::measure`], turning an
+    /// `Error::Synthesis` from the circuit into [`ReportRow::NotApplicable`].
+    pub fn measure<C: Circuit<Fp>>(
+        name: &str,
+        n: usize,
+        k: u32,
+        circuit: C,
+        instance: &[Fp],
+    ) -> Result<Self, Error> {
+        match CostReport::measure(name, n, k, circuit, instance) {
+            Ok(report) => Ok(ReportRow::Measured(report)),
+            Err(Error::Synthesis) => Ok(ReportRow::NotApplicable {
+                name: name.to_string(),
+                n,
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn cost(&self) -> Option<&CostReport> {
+        match self {
+            ReportRow::Measured(report) => Some(report),
+            ReportRow::NotApplicable { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ReportRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportRow::Measured(report) => write!(f, "{}", report),
+            ReportRow::NotApplicable { name, n } => write!(f, "{:<14} {:>6} {:>3}", name, n, "n/a"),
+        }
+    }
+}
+
+/// Measures `e3_fibonacci_ex1` and `e3_fibonacci_ex2` for each `F(n)` in
+/// `lengths`, with the seeds `F(0) = F(1) = 1`.
+///
+/// Each circuit runs at its own smallest `k`.
+pub fn fibonacci_report(lengths: &[usize]) -> Result<Vec<ReportRow>, Error> {
+    let (a, b) = (Fp::one(), Fp::one());
+    let mut reports = Vec::new();
+
+    for &n in lengths {
+        // ex1は最初の行がF(2)なので、n < 2はn/aになる
+        let circuit = MultiColumn::<Fp>::new(n, None);
+        let instance = circuit.instance(a, b);
+        reports.push(ReportRow::measure(
+            "multi-column",
+            n,
+            circuit.min_k(),
+            circuit,
+            &instance,
+        )?);
+
+        let circuit = SingleColumn::<Fp>::new(n, None);
+        let instance = circuit.instance(a, b);
+        reports.push(ReportRow::measure(
+            "single-column",
+            n,
+            circuit.min_k(),
+            circuit,
+            &instance,
+        )?);
+    }
+
+    Ok(reports)
+}
+
+/// Records the highest row the floor planner writes to. Values are ignored.
+struct RowCounter<F> {
+    rows: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F> RowCounter<F> {
+    fn touch(&mut self, row: usize) {
+        self.rows = self.rows.max(row + 1);
+    }
+}
+
+impl<F: Field> Assignment<F> for RowCounter<F> {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    // lookup tableの残りを埋めるだけなので、使った行には数えない
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[test]
+fn fibonacci_report_layouts() {
+    let reports = fibonacci_report(&[9, 40]).unwrap();
+    assert_eq!(reports.len(), 4);
+
+    for pair in reports.chunks(2) {
+        let (multi, single) = (pair[0].cost().unwrap(), pair[1].cost().unwrap());
+        assert_eq!(multi.n, single.n);
+
+        // 3列で1 regionずつ vs 1列をRotationで
+        assert_eq!((multi.advice_columns, single.advice_columns), (3, 1));
+        assert_eq!(multi.rows, multi.n - 1);
+        assert_eq!(single.rows, single.n + 1);
+        // a, b, c, instance, 定数のfixed vs advice, instance, 定数のfixed
+        assert_eq!(multi.permutation_columns, 5);
+        assert_eq!(single.permutation_columns, 3);
+        assert!(multi.proof_size > single.proof_size);
+    }
+
+    assert!(reports[0].to_string().starts_with("multi-column"));
+    assert!(CostReport::header().starts_with("circuit"));
+
+    // ex1はF(1)を作れないので、その行はn/aになる
+    let reports = fibonacci_report(&[1]).unwrap();
+    assert!(reports[0].cost().is_none());
+    assert!(reports[0].to_string().ends_with("n/a"));
+    assert_eq!(reports[1].cost().unwrap().n, 1);
+}
@@ -0,0 +1,295 @@
+//! Negative tests for the example circuits.
+//!
+//! A sound circuit must reject the proof as soon as a single public input or a
+//! single advice cell is changed. The helpers here take an honest circuit and
+//! instance, perturb each of them one at a time and check that `MockProver`
+//! reports the expected `VerifyFailure`s.
+//!
+//! Advice cells are perturbed through a witness-override hook: `Tampered<C>`
+//! runs `C` unchanged, except that its floor planner wraps the `Assignment`
+//! and adds one to the n-th advice value it sees. This works for every circuit
+//! without touching its chips.
+//!
+//! [`mock_verify`] and [`min_k`] are shared by the tests and circuits that run
+//! at their smallest `k`.
+
+use std::{cell::Cell, marker::PhantomData};
+
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{Layouter, Value},
+    dev::{MockProver, VerifyFailure},
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+
+thread_local! {
+    // FloorPlanner::synthesize is a static function, so the hook is configured here.
+    static TARGET: Cell<Option<usize>> = Cell::new(None);
+    static ADVICE_COUNT: Cell<usize> = Cell::new(0);
+}
+
+/// The smallest `k` whose usable rows fit `rows` rows of a circuit configured
+/// into `meta`.
+pub fn min_k<F: FieldExt>(rows: usize, meta: &ConstraintSystem<F>) -> u32 {
+    // 最後のblinding_factors + 1行はblindingに使われるので使えない
+    let rows = rows + meta.blinding_factors() + 1;
+
+    let mut k = 1;
+    while (1 << k) < rows {
+        k += 1;
+    }
+    k
+}
+
+/// Runs `circuit` on `MockProver` and returns the result of `verify`, for
+/// tests that check many assignments against the same `k`.
+pub fn mock_verify<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+) -> Result<(), Vec<VerifyFailure>> {
+    MockProver::run(k, circuit, instance).unwrap().verify()
+}
+
+/// Checks that the honest `circuit` verifies, and that changing any single
+/// public input or advice cell makes it fail.
+pub fn assert_sound<F: FieldExt, C: Circuit<F>>(k: u32, circuit: &C, instance: Vec<Vec<F>>) {
+    let prover = MockProver::run(k, circuit, instance.clone()).unwrap();
+    assert_eq!(prover.verify(), Ok(()), "the honest assignment must verify");
+
+    assert_public_inputs_bound(k, circuit, instance.clone());
+    assert_witnesses_bound(k, circuit, instance);
+}
+
+/// Adds one to each public input in turn and expects a `Permutation` failure,
+/// i.e. the instance cell is no longer equal to the advice cell it is bound to.
+pub fn assert_public_inputs_bound<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+) {
+    for column in 0..instance.len() {
+        for row in 0..instance[column].len() {
+            let mut tampered = instance.clone();
+            tampered[column][row] += F::one();
+
+            let prover = MockProver::run(k, circuit, tampered).unwrap();
+            let failures = prover.verify().expect_err(&format!(
+                "changing public input {} of instance column {} was accepted",
+                row, column
+            ));
+            assert!(
+                failures
+                    .iter()
+                    .all(|failure| matches!(failure, VerifyFailure::Permutation { .. })),
+                "changing public input {} of instance column {} failed for the wrong reason: {:?}",
+                row,
+                column,
+                failures
+            );
+        }
+    }
+}
+
+/// Adds one to each advice cell in turn and expects the change to break a
+/// gate, a copy constraint or a lookup.
+pub fn assert_witnesses_bound<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+) {
+    let advice_cells = count_advice_assignments(k, circuit, instance.clone());
+    assert!(advice_cells > 0, "the circuit assigns no advice cells");
+
+    for index in 0..advice_cells {
+        TARGET.with(|target| target.set(Some(index)));
+        let prover = MockProver::run(k, &Tampered(circuit), instance.clone());
+        TARGET.with(|target| target.set(None));
+
+        let failures = prover.unwrap().verify().expect_err(&format!(
+            "changing advice assignment {} was accepted",
+            index
+        ));
+        assert!(
+            failures.iter().all(|failure| matches!(
+                failure,
+                VerifyFailure::ConstraintNotSatisfied { .. }
+                    | VerifyFailure::Permutation { .. }
+                    | VerifyFailure::Lookup { .. }
+            )),
+            "changing advice assignment {} failed for the wrong reason: {:?}",
+            index,
+            failures
+        );
+    }
+}
+
+/// Number of advice values the floor planner actually writes for `circuit`.
+pub fn count_advice_assignments<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+) -> usize {
+    TARGET.with(|target| target.set(None));
+    MockProver::run(k, &Tampered(circuit), instance).unwrap();
+    ADVICE_COUNT.with(|count| count.get())
+}
+
+/// Runs the wrapped circuit with the witness-override hook installed.
+pub struct Tampered<'a, C>(pub &'a C);
+
+impl<'a, F: FieldExt, C: Circuit<F>> Circuit<F> for Tampered<'a, C> {
+    type Config = C::Config;
+    type FloorPlanner = TamperingFloorPlanner<C::FloorPlanner>;
+
+    // Tampered circuits are only run through MockProver, which never strips
+    // the witnesses, so this just keeps the borrowed circuit.
+    fn without_witnesses(&self) -> Self {
+        Tampered(self.0)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+/// Lays out the circuit with `P`, perturbing the targeted advice assignment.
+pub struct TamperingFloorPlanner<P>(PhantomData<P>);
+
+impl<P: FloorPlanner> FloorPlanner for TamperingFloorPlanner<P> {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut cs = TamperingAssignment {
+            cs,
+            target: TARGET.with(|target| target.get()),
+            advice_count: 0,
+            _marker: PhantomData,
+        };
+        let result = P::synthesize(&mut cs, circuit, config, constants);
+        ADVICE_COUNT.with(|count| count.set(cs.advice_count));
+
+        result
+    }
+}
+
+struct TamperingAssignment<'a, F: Field, CS: Assignment<F>> {
+    cs: &'a mut CS,
+    target: Option<usize>,
+    advice_count: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<'a, F: Field, CS: Assignment<F>> Assignment<F> for TamperingAssignment<'a, F, CS> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.enter_region(name_fn)
+    }
+
+    fn exit_region(&mut self) {
+        self.cs.exit_region()
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let tamper = self.target == Some(self.advice_count);
+        self.advice_count += 1;
+
+        self.cs.assign_advice(annotation, column, row, || {
+            let value: Value<Assigned<F>> = to().map(|v| v.into());
+            if tamper {
+                value.map(|v| v + Assigned::from(F::one()))
+            } else {
+                value
+            }
+        })
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_fixed(annotation, column, row, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}